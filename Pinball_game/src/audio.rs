@@ -0,0 +1,145 @@
+// 音效：用Web Audio的振荡器和音量包络实时合成，不需要音频文件
+//
+// GameState在模拟中把要播放的音效放进队列，PinballGame每一帧取出后交给AudioPlayer播放
+use web_sys::{AudioContext, AudioContextState, GainNode, OscillatorType};
+
+pub const DEFAULT_VOLUME: f64 = 0.5;
+const ATTACK_TIME: f64 = 0.005; // 音量从0升到最大的时间（秒），避免爆音
+const SILENCE: f64 = 0.0001; // 指数衰减不能到0，衰减到这个音量就听不见了
+
+// 音效种类
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sound {
+    Bounce,     // 球碰到墙壁或没有碎的砖块
+    PaddleHit,  // 球碰到挡板
+    BrickBreak, // 砖块碎裂
+    LifeLost,   // 失去一条生命
+    LevelClear, // 本关完成
+}
+
+// 音效中的一个音：频率从from滑到to，音量迅速升起后按指数衰减
+struct Tone {
+    wave: OscillatorType,
+    from: f64,     // 起始频率（Hz）
+    to: f64,       // 结束频率（Hz）
+    start: f64,    // 相对音效开始的时间（秒）
+    duration: f64, // 持续时间（秒）
+    gain: f64,     // 最大音量（0到1）
+}
+
+impl Sound {
+    fn tones(self) -> Vec<Tone> {
+        let tone = |wave, from, to, start, duration, gain| Tone { wave, from, to, start, duration, gain };
+        match self {
+            Sound::Bounce => vec![tone(OscillatorType::Square, 520.0, 520.0, 0.0, 0.05, 0.12)],
+            Sound::PaddleHit => vec![tone(OscillatorType::Square, 260.0, 390.0, 0.0, 0.08, 0.2)],
+            Sound::BrickBreak => vec![
+                tone(OscillatorType::Triangle, 880.0, 1320.0, 0.0, 0.08, 0.25),
+                tone(OscillatorType::Square, 1320.0, 660.0, 0.03, 0.08, 0.08),
+            ],
+            Sound::LifeLost => vec![tone(OscillatorType::Sawtooth, 330.0, 70.0, 0.0, 0.6, 0.25)],
+            // C大调琶音
+            Sound::LevelClear => [523.25, 659.25, 783.99, 1046.5]
+                .iter()
+                .enumerate()
+                .map(|(index, &frequency)| tone(OscillatorType::Triangle, frequency, frequency, index as f64 * 0.1, 0.25, 0.25))
+                .collect(),
+        }
+    }
+}
+
+// 音效播放器，负责音量和静音
+pub struct AudioPlayer {
+    context: Option<(AudioContext, GainNode)>, // 音频上下文和总音量节点，第一次播放时创建
+    volume: f64,
+    muted: bool,
+}
+
+impl Default for AudioPlayer {
+    fn default() -> Self {
+        AudioPlayer { context: None, volume: DEFAULT_VOLUME, muted: false }
+    }
+}
+
+impl AudioPlayer {
+    pub fn set_volume(&mut self, volume: f64) {
+        self.volume = if volume.is_finite() { volume.clamp(0.0, 1.0) } else { DEFAULT_VOLUME };
+        if let Some((_, master)) = &self.context {
+            master.gain().set_value(self.volume as f32);
+        }
+    }
+
+    pub fn volume(&self) -> f64 {
+        self.volume
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    // 播放一帧内的音效，同一帧内重复的音效只播放一次（例如爆炸砖块连锁击碎多块砖）
+    pub fn play(&mut self, sounds: &[Sound]) {
+        if self.muted || self.volume == 0.0 || sounds.is_empty() {
+            return;
+        }
+        let Some((context, master)) = self.context() else {
+            return;
+        };
+
+        // 浏览器在用户操作页面之前会挂起音频上下文，玩家点击开始后才能恢复
+        if context.state() == AudioContextState::Suspended {
+            let _ = context.resume();
+        }
+
+        let mut played = Vec::new();
+        for &sound in sounds {
+            if played.contains(&sound) {
+                continue;
+            }
+            played.push(sound);
+
+            let now = context.current_time();
+            for tone in sound.tones() {
+                let _ = play_tone(context, master, &tone, now);
+            }
+        }
+    }
+
+    // 取得音频上下文，浏览器不支持Web Audio时返回None
+    fn context(&mut self) -> Option<&(AudioContext, GainNode)> {
+        if self.context.is_none() {
+            let context = AudioContext::new().ok()?;
+            let master = context.create_gain().ok()?;
+            master.gain().set_value(self.volume as f32);
+            master.connect_with_audio_node(&context.destination()).ok()?;
+            self.context = Some((context, master));
+        }
+        self.context.as_ref()
+    }
+}
+
+// 为一个音创建振荡器和音量包络，播放结束后节点由浏览器自动回收
+fn play_tone(context: &AudioContext, master: &GainNode, tone: &Tone, now: f64) -> Result<(), wasm_bindgen::JsValue> {
+    let start = now + tone.start;
+    let end = start + tone.duration;
+
+    let oscillator = context.create_oscillator()?;
+    oscillator.set_type(tone.wave);
+    oscillator.frequency().set_value_at_time(tone.from as f32, start)?;
+    oscillator.frequency().exponential_ramp_to_value_at_time(tone.to as f32, end)?;
+
+    let envelope = context.create_gain()?;
+    envelope.gain().set_value_at_time(0.0, start)?;
+    envelope.gain().linear_ramp_to_value_at_time(tone.gain as f32, start + ATTACK_TIME)?;
+    envelope.gain().exponential_ramp_to_value_at_time(SILENCE as f32, end)?;
+
+    oscillator.connect_with_audio_node(&envelope)?;
+    envelope.connect_with_audio_node(master)?;
+    oscillator.start_with_when(start)?;
+    oscillator.stop_with_when(end)?;
+    Ok(())
+}
@@ -0,0 +1,88 @@
+// 球
+use std::f64::consts::TAU;
+
+pub const MAX_SPIN: f64 = 10.0; // 球旋转速度的上限（弧度/秒）
+const SPIN_CURVE: f64 = 0.05; // 每单位旋转速度让运动方向每秒偏转的角度（弧度）
+const SPIN_DECAY: f64 = 1.0; // 旋转速度每秒按e^-SPIN_DECAY衰减
+const MIN_SPIN: f64 = 0.05; // 旋转速度低于这个值时视为停止旋转
+
+#[derive(Clone, Copy)]
+pub struct Ball {
+    pub x: f64,
+    pub y: f64,
+    pub dx: f64,
+    pub dy: f64,
+    pub prev_x: f64, // 上一个物理步结束时球的位置，用于渲染插值
+    pub prev_y: f64,
+    pub stuck: Option<f64>, // 球粘在挡板上时，球心相对挡板左端的偏移
+    pub spin: f64,          // 旋转速度（弧度/秒），正值为顺时针，让运动方向向顺时针方向偏转
+    pub angle: f64,         // 当前转过的角度，用于绘制旋转标记
+    pub reached_top: bool,  // 是否已经到达过顶部几行砖块
+}
+
+impl Ball {
+    // 创建一个静止在(x, y)的球
+    pub fn at(x: f64, y: f64) -> Self {
+        Ball { x, y, dx: 0.0, dy: 0.0, prev_x: x, prev_y: y, stuck: None, spin: 0.0, angle: 0.0, reached_top: false }
+    }
+
+    pub fn speed(&self) -> f64 {
+        (self.dx.powi(2) + self.dy.powi(2)).sqrt()
+    }
+
+    // 把球放到(x, y)，同时清除插值用的上一步位置，避免渲染出拖影
+    pub fn place(&mut self, x: f64, y: f64) {
+        self.x = x;
+        self.y = y;
+        self.prev_x = x;
+        self.prev_y = y;
+    }
+
+    // 在上一步与当前步之间插值出渲染位置，alpha为0到1
+    pub fn interpolated(&self, alpha: f64) -> (f64, f64) {
+        (self.prev_x + (self.x - self.prev_x) * alpha, self.prev_y + (self.y - self.prev_y) * alpha)
+    }
+
+    // 沿当前方向加速amount，速度不超过max
+    pub fn speed_up(&mut self, amount: f64, max: f64) {
+        let speed = self.speed();
+        let target = (speed + amount).min(max);
+        if speed == 0.0 || target <= speed {
+            return;
+        }
+
+        let scale = target / speed;
+        self.dx *= scale;
+        self.dy *= scale;
+    }
+
+    // 旋转让球的运动方向逐渐偏转，速度大小不变；旋转随时间衰减
+    pub fn apply_spin(&mut self, delta_time: f64) {
+        if self.spin == 0.0 {
+            return;
+        }
+
+        let (sin, cos) = (self.spin * SPIN_CURVE * delta_time).sin_cos();
+        (self.dx, self.dy) = (self.dx * cos - self.dy * sin, self.dx * sin + self.dy * cos);
+        self.angle = (self.angle + self.spin * delta_time).rem_euclid(TAU);
+
+        self.spin *= (-SPIN_DECAY * delta_time).exp();
+        if self.spin.abs() < MIN_SPIN {
+            self.spin = 0.0;
+        }
+    }
+
+    // 以当前球为基础分裂出一个方向旋转了angle（弧度）的新球
+    pub fn split(&self, angle: f64) -> Ball {
+        // 粘在挡板上的球按竖直向上的方向分裂
+        let (dx, dy) = if self.stuck.is_some() { (0.0, -self.speed()) } else { (self.dx, self.dy) };
+        let (sin, cos) = angle.sin_cos();
+
+        Ball {
+            dx: dx * cos - dy * sin,
+            dy: dx * sin + dy * cos,
+            stuck: None,
+            ..*self
+        }
+    }
+}
@@ -0,0 +1,267 @@
+// 连续碰撞检测（time of impact）
+//
+// 球在一个物理步内沿直线运动，这里计算它最早在什么时间碰到某个物体，
+// 而不是只在步末检查是否重叠，从而避免高速时穿过砖块或挡板
+
+// 轴对齐矩形
+#[derive(Clone, Copy)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+// 一次碰撞：发生的时间（秒，相对于本次检测的起点）和碰撞面的法线
+#[derive(Clone, Copy)]
+pub struct Hit {
+    pub time: f64,
+    pub normal_x: f64,
+    pub normal_y: f64,
+}
+
+// 球与物体重叠的情况：把球推出去的方向和需要推出的距离
+#[derive(Clone, Copy)]
+pub struct Contact {
+    pub normal_x: f64,
+    pub normal_y: f64,
+    pub depth: f64,
+}
+
+// 计算以(x, y)为圆心、以(dx, dy)运动的圆在max_time内与矩形的最早碰撞
+//
+// 把矩形向外扩展一个半径，问题就变成了球心射线与扩展矩形的相交（slab法）。
+// 扩展后的矩形四角实际上是以矩形顶点为圆心的圆弧，球心射线落在角上时改为与圆弧求交，
+// 法线从顶点指向碰撞时的球心。起点已经与矩形重叠或者正在远离矩形时不算碰撞
+pub fn sweep_circle_rect(x: f64, y: f64, dx: f64, dy: f64, radius: f64, rect: &Rect, max_time: f64) -> Option<Hit> {
+    let left = rect.x - radius;
+    let right = rect.x + rect.width + radius;
+    let top = rect.y - radius;
+    let bottom = rect.y + rect.height + radius;
+
+    // 分别计算进入和离开两个方向的时间区间
+    let (enter_x, exit_x) = slab(x, dx, left, right)?;
+    let (enter_y, exit_y) = slab(y, dy, top, bottom)?;
+
+    let enter = f64::max(enter_x, enter_y);
+    let exit = f64::min(exit_x, exit_y);
+
+    if enter > exit || exit < 0.0 || enter > max_time {
+        return None;
+    }
+
+    // 进入扩展矩形的位置在角上时，只有碰到顶点处的圆弧才算碰撞；
+    // 起点在角上但还没碰到圆弧时同样按圆弧计算
+    let time = enter.max(0.0);
+    let (hit_x, hit_y) = (x + dx * time, y + dy * time);
+    let corner_x = if hit_x < rect.x {
+        Some(rect.x)
+    } else if hit_x > rect.x + rect.width {
+        Some(rect.x + rect.width)
+    } else {
+        None
+    };
+    let corner_y = if hit_y < rect.y {
+        Some(rect.y)
+    } else if hit_y > rect.y + rect.height {
+        Some(rect.y + rect.height)
+    } else {
+        None
+    };
+    if let (Some(corner_x), Some(corner_y)) = (corner_x, corner_y) {
+        return sweep_circle_point(x, y, dx, dy, radius, (corner_x, corner_y), max_time);
+    }
+
+    if enter < 0.0 {
+        return None;
+    }
+
+    // 最后进入的那个方向就是碰撞面
+    let (normal_x, normal_y) = if enter_x > enter_y {
+        (-dx.signum(), 0.0)
+    } else {
+        (0.0, -dy.signum())
+    };
+
+    Some(Hit { time: enter, normal_x, normal_y })
+}
+
+// 计算运动的圆在max_time内碰到点(point_x, point_y)的时间，法线从该点指向碰撞时的圆心
+fn sweep_circle_point(x: f64, y: f64, dx: f64, dy: f64, radius: f64, (point_x, point_y): (f64, f64), max_time: f64) -> Option<Hit> {
+    // 解 |(x, y) + (dx, dy)·t - point|² = radius² 中较小的根
+    let (offset_x, offset_y) = (x - point_x, y - point_y);
+    let a = dx * dx + dy * dy;
+    let b = offset_x * dx + offset_y * dy;
+    let c = offset_x * offset_x + offset_y * offset_y - radius * radius;
+
+    // 已经重叠或者正在远离
+    if c <= 0.0 || b >= 0.0 {
+        return None;
+    }
+
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let time = (-b - discriminant.sqrt()) / a;
+    if time > max_time {
+        return None;
+    }
+
+    let normal_x = (offset_x + dx * time) / radius;
+    let normal_y = (offset_y + dy * time) / radius;
+    Some(Hit { time, normal_x, normal_y })
+}
+
+// 检查圆心在(x, y)的圆是否与矩形重叠，重叠时返回把圆推出矩形的最短方向和距离
+pub fn penetration(x: f64, y: f64, radius: f64, rect: &Rect) -> Option<Contact> {
+    // 矩形上离圆心最近的点
+    let closest_x = x.clamp(rect.x, rect.x + rect.width);
+    let closest_y = y.clamp(rect.y, rect.y + rect.height);
+    let (offset_x, offset_y) = (x - closest_x, y - closest_y);
+    let distance = (offset_x * offset_x + offset_y * offset_y).sqrt();
+
+    if distance >= radius {
+        return None;
+    }
+    if distance > 0.0 {
+        return Some(Contact { normal_x: offset_x / distance, normal_y: offset_y / distance, depth: radius - distance });
+    }
+
+    // 圆心在矩形内部：从最近的一条边推出去
+    let exits = [
+        (x - rect.x, -1.0, 0.0),
+        (rect.x + rect.width - x, 1.0, 0.0),
+        (y - rect.y, 0.0, -1.0),
+        (rect.y + rect.height - y, 0.0, 1.0),
+    ];
+    let (distance, normal_x, normal_y) = exits.into_iter().fold(exits[0], |best, exit| if exit.0 < best.0 { exit } else { best });
+    Some(Contact { normal_x, normal_y, depth: distance + radius })
+}
+
+// 计算一维上从pos以速度vel运动时，位于[min, max]区间内的时间段
+fn slab(pos: f64, vel: f64, min: f64, max: f64) -> Option<(f64, f64)> {
+    if vel == 0.0 {
+        // 该方向上不动：要么一直在区间内，要么永远不会进入
+        return if pos > min && pos < max {
+            Some((f64::NEG_INFINITY, f64::INFINITY))
+        } else {
+            None
+        };
+    }
+
+    let t1 = (min - pos) / vel;
+    let t2 = (max - pos) / vel;
+    Some((f64::min(t1, t2), f64::max(t1, t2)))
+}
+
+// 计算球心碰到竖直直线wall_x的时间，只有朝着直线运动时才会发生碰撞
+pub fn sweep_vertical_line(x: f64, dx: f64, wall_x: f64, normal_x: f64, max_time: f64) -> Option<Hit> {
+    if dx * normal_x >= 0.0 {
+        return None;
+    }
+
+    let time = ((wall_x - x) / dx).max(0.0);
+    if time > max_time {
+        return None;
+    }
+
+    Some(Hit { time, normal_x, normal_y: 0.0 })
+}
+
+// 计算球心碰到水平直线wall_y的时间，只有朝着直线运动时才会发生碰撞
+pub fn sweep_horizontal_line(y: f64, dy: f64, wall_y: f64, normal_y: f64, max_time: f64) -> Option<Hit> {
+    if dy * normal_y >= 0.0 {
+        return None;
+    }
+
+    let time = ((wall_y - y) / dy).max(0.0);
+    if time > max_time {
+        return None;
+    }
+
+    Some(Hit { time, normal_x: 0.0, normal_y })
+}
+
+// 沿法线反射速度
+pub fn reflect(dx: f64, dy: f64, normal_x: f64, normal_y: f64) -> (f64, f64) {
+    let dot = dx * normal_x + dy * normal_y;
+    (dx - 2.0 * dot * normal_x, dy - 2.0 * dot * normal_y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RADIUS: f64 = 8.0;
+    const BRICK: Rect = Rect { x: 100.0, y: 100.0, width: 50.0, height: 20.0 };
+
+    #[test]
+    fn hits_the_face_it_enters_through() {
+        // 从正下方向上运动，碰到底面
+        let hit = sweep_circle_rect(125.0, 160.0, 0.0, -100.0, RADIUS, &BRICK, 1.0).unwrap();
+        assert!((hit.time - (160.0 - 120.0 - RADIUS) / 100.0).abs() < 1e-9);
+        assert_eq!((hit.normal_x, hit.normal_y), (0.0, 1.0));
+
+        // 从左侧向右运动，碰到左侧面
+        let hit = sweep_circle_rect(50.0, 110.0, 100.0, 0.0, RADIUS, &BRICK, 1.0).unwrap();
+        assert!((hit.time - (100.0 - RADIUS - 50.0) / 100.0).abs() < 1e-9);
+        assert_eq!((hit.normal_x, hit.normal_y), (-1.0, 0.0));
+    }
+
+    #[test]
+    fn ignores_hits_beyond_max_time_or_moving_away() {
+        assert!(sweep_circle_rect(125.0, 160.0, 0.0, -100.0, RADIUS, &BRICK, 0.1).is_none());
+        assert!(sweep_circle_rect(125.0, 160.0, 0.0, 100.0, RADIUS, &BRICK, 1.0).is_none());
+    }
+
+    #[test]
+    fn corner_hit_uses_the_normal_from_the_corner() {
+        // 沿对角线正对左上角运动，法线指向左上方
+        let hit = sweep_circle_rect(70.0, 70.0, 100.0, 100.0, RADIUS, &BRICK, 1.0).unwrap();
+        let expected = -(0.5_f64).sqrt();
+        assert!((hit.normal_x - expected).abs() < 1e-9);
+        assert!((hit.normal_y - expected).abs() < 1e-9);
+
+        // 碰撞时球心到顶点的距离正好是半径
+        let (x, y) = (70.0 + 100.0 * hit.time, 70.0 + 100.0 * hit.time);
+        assert!((((x - 100.0).powi(2) + (y - 100.0).powi(2)).sqrt() - RADIUS).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ball_grazing_past_a_corner_misses() {
+        // 穿过扩展矩形的角，但离顶点始终超过一个半径
+        assert!(sweep_circle_rect(82.5, 102.5, 100.0, -100.0, RADIUS, &BRICK, 1.0).is_none());
+    }
+
+    #[test]
+    fn reflect_mirrors_the_velocity() {
+        assert_eq!(reflect(3.0, -4.0, 0.0, 1.0), (3.0, 4.0));
+        assert_eq!(reflect(3.0, -4.0, -1.0, 0.0), (-3.0, -4.0));
+    }
+
+    #[test]
+    fn penetration_pushes_out_the_shortest_way() {
+        assert!(penetration(125.0, 80.0, RADIUS, &BRICK).is_none());
+
+        // 与底面重叠3个像素
+        let contact = penetration(125.0, 125.0, RADIUS, &BRICK).unwrap();
+        assert_eq!((contact.normal_x, contact.normal_y), (0.0, 1.0));
+        assert!((contact.depth - 3.0).abs() < 1e-9);
+
+        // 球心在矩形内，离顶面最近
+        let contact = penetration(125.0, 102.0, RADIUS, &BRICK).unwrap();
+        assert_eq!((contact.normal_x, contact.normal_y), (0.0, -1.0));
+        assert!((contact.depth - (2.0 + RADIUS)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,139 @@
+// 游戏场地配置
+//
+// 创建PinballGame时传入，之后不再改变。场地、挡板、球和砖块的位置尺寸都由它推算，
+// 单位是游戏内部的逻辑像素
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct GameConfig {
+    #[wasm_bindgen(js_name = canvasWidth)]
+    pub canvas_width: f64, // 场地宽度
+    #[wasm_bindgen(js_name = canvasHeight)]
+    pub canvas_height: f64, // 场地高度
+    #[wasm_bindgen(js_name = ballRadius)]
+    pub ball_radius: f64,
+    #[wasm_bindgen(js_name = paddleWidth)]
+    pub paddle_width: f64, // 没有道具效果时的挡板宽度
+    #[wasm_bindgen(js_name = paddleHeight)]
+    pub paddle_height: f64,
+    #[wasm_bindgen(js_name = paddleOffset)]
+    pub paddle_offset: f64, // 挡板顶面到场地底部的距离
+    #[wasm_bindgen(js_name = brickWidth)]
+    pub brick_width: f64,
+    #[wasm_bindgen(js_name = brickHeight)]
+    pub brick_height: f64,
+    #[wasm_bindgen(js_name = brickRows)]
+    pub brick_rows: usize,
+    #[wasm_bindgen(js_name = brickCols)]
+    pub brick_cols: usize,
+    #[wasm_bindgen(js_name = brickTopOffset)]
+    pub brick_top_offset: f64, // 第一行砖块到场地顶部的距离，上方留给信息栏
+    #[wasm_bindgen(js_name = brickPadding)]
+    pub brick_padding: f64, // 砖块之间以及砖块与左边缘的间距
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            canvas_width: 440.0,
+            canvas_height: 330.0,
+            ball_radius: 8.0,
+            paddle_width: 80.0,
+            paddle_height: 12.0,
+            paddle_offset: 30.0,
+            brick_width: 50.0,
+            brick_height: 20.0,
+            brick_rows: 5,
+            brick_cols: 7,
+            brick_top_offset: 40.0,
+            brick_padding: 5.0,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl GameConfig {
+    // 创建默认配置（440×330的场地），JS端可以在此基础上修改各项
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> GameConfig {
+        GameConfig::default()
+    }
+}
+
+impl GameConfig {
+    // 挡板顶面的y坐标
+    pub fn paddle_y(&self) -> f64 {
+        self.canvas_height - self.paddle_offset
+    }
+
+    // 砖块必须放在挡板上方留出发球空间的区域内
+    pub fn brick_area_bottom(&self) -> f64 {
+        self.paddle_y() - self.ball_radius * 4.0
+    }
+
+    // 检查各项尺寸能否组成一个可以玩的场地
+    pub fn validate(&self) -> Result<(), String> {
+        let sizes = [
+            self.canvas_width,
+            self.canvas_height,
+            self.ball_radius,
+            self.paddle_width,
+            self.paddle_height,
+            self.brick_width,
+            self.brick_height,
+        ];
+        if sizes.iter().any(|size| !size.is_finite() || *size <= 0.0) {
+            return Err("场地、球、挡板和砖块的尺寸必须大于0".to_string());
+        }
+        let offsets = [self.paddle_offset, self.brick_padding, self.brick_top_offset];
+        if offsets.iter().any(|offset| !offset.is_finite() || *offset < 0.0) {
+            return Err("paddleOffset、brickPadding和brickTopOffset必须是不小于0的数".to_string());
+        }
+        if self.paddle_width >= self.canvas_width {
+            return Err("挡板宽度必须小于场地宽度".to_string());
+        }
+        if self.paddle_offset < self.paddle_height || self.paddle_offset >= self.canvas_height {
+            return Err("paddleOffset必须不小于挡板高度并且小于场地高度".to_string());
+        }
+        if self.brick_rows == 0 || self.brick_cols == 0 {
+            return Err("砖块的行数和列数必须大于0".to_string());
+        }
+
+        // 砖块网格需要放得进场地，并且在挡板上方留出发球空间
+        let grid_right = self.brick_cols as f64 * (self.brick_width + self.brick_padding) + self.brick_padding;
+        let grid_bottom = self.brick_top_offset + self.brick_rows as f64 * (self.brick_height + self.brick_padding);
+        if grid_right > self.canvas_width || grid_bottom > self.brick_area_bottom() {
+            return Err(format!(
+                "{}行{}列的砖块超出了可放置区域（{}×{}）",
+                self.brick_rows,
+                self.brick_cols,
+                self.canvas_width,
+                self.brick_area_bottom()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_rejects_bad_offsets() {
+        assert!(GameConfig::default().validate().is_ok());
+
+        let configs = [
+            GameConfig { paddle_offset: f64::NAN, ..GameConfig::default() },
+            GameConfig { brick_padding: f64::NAN, ..GameConfig::default() },
+            GameConfig { brick_padding: -5.0, ..GameConfig::default() },
+            GameConfig { brick_top_offset: f64::INFINITY, ..GameConfig::default() },
+            GameConfig { brick_top_offset: -40.0, ..GameConfig::default() },
+        ];
+        for config in configs {
+            assert!(config.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,66 @@
+// 游戏事件：模拟过程中发生的重要事情，由PinballGame在每一帧取出并转发给JS注册的回调
+use js_sys::{Object, Reflect};
+use wasm_bindgen::JsValue;
+
+// 事件种类
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameEventKind {
+    GameStarted,    // 开始新的一局
+    BrickDestroyed, // 一块砖被击碎
+    LifeLost,       // 最后一个球掉出底部，失去一条生命
+    LevelCleared,   // 本关的砖块全部击碎
+    GameOver,       // 生命用完
+}
+
+impl GameEventKind {
+    pub const ALL: [GameEventKind; 5] = [
+        GameEventKind::GameStarted,
+        GameEventKind::BrickDestroyed,
+        GameEventKind::LifeLost,
+        GameEventKind::LevelCleared,
+        GameEventKind::GameOver,
+    ];
+
+    // JS端注册回调时使用的名字
+    pub fn name(self) -> &'static str {
+        match self {
+            GameEventKind::GameStarted => "gameStarted",
+            GameEventKind::BrickDestroyed => "brickDestroyed",
+            GameEventKind::LifeLost => "lifeLost",
+            GameEventKind::LevelCleared => "levelCleared",
+            GameEventKind::GameOver => "gameOver",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+}
+
+// 一个事件和事件发生后的游戏状态
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GameEvent {
+    pub kind: GameEventKind,
+    pub score: u32,
+    pub level: u32,
+    pub lives: u32,
+    pub remaining_bricks: usize, // 本关还需要击碎的砖块数
+}
+
+impl GameEvent {
+    // 转换成传给JS回调的对象，例如 { type: "brickDestroyed", score: 120, level: 2, lives: 3, remainingBricks: 17 }
+    pub fn to_js(self) -> JsValue {
+        let object = Object::new();
+        let fields = [
+            ("type", JsValue::from_str(self.kind.name())),
+            ("score", JsValue::from(self.score)),
+            ("level", JsValue::from(self.level)),
+            ("lives", JsValue::from(self.lives)),
+            ("remainingBricks", JsValue::from(self.remaining_bricks as u32)),
+        ];
+        for (key, value) in fields {
+            Reflect::set(&object, &JsValue::from_str(key), &value).unwrap();
+        }
+        object.into()
+    }
+}
@@ -0,0 +1,1436 @@
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+
+use crate::audio::Sound;
+use crate::ball::{Ball, MAX_SPIN};
+use crate::collision::{penetration, reflect, sweep_circle_rect, sweep_horizontal_line, sweep_vertical_line, Hit, Rect};
+use crate::config::GameConfig;
+use crate::event::{GameEvent, GameEventKind};
+use crate::highscore::{format_date, HighScore, HighScoreTable, MemoryStorage, MAX_INITIALS};
+use crate::level::Level;
+use crate::particle::ParticleSystem;
+use crate::powerup::{
+    add_effect, tick_effects, ActiveEffect, Capsule, LaserBolt, PowerUpKind, DROP_CHANCE, LASER_COOLDOWN, LASER_LENGTH,
+};
+use crate::replay::{Playback, Record, Recorder, Replay};
+use crate::speed::{speed_range, PADDLE_HIT_SPEEDUP, SPEEDUP_PER_SECOND, TOP_ROWS, TOP_ROWS_SPEEDUP};
+
+// 游戏常量（场地和物体的尺寸见GameConfig）
+const WIDE_PADDLE_SCALE: f64 = 1.5; // 变宽道具生效时挡板宽度的倍数
+const NARROW_PADDLE_SCALE: f64 = 0.6; // 变窄道具生效时挡板宽度的倍数
+const SLOW_BALL_SCALE: f64 = 0.6; // 减速道具生效时球的速度倍数
+const PADDLE_MAX_SPEED: f64 = 480.0; // 键盘控制时挡板的最大速度（像素/秒）
+const PADDLE_ACCELERATION: f64 = 2400.0; // 键盘控制时挡板的加速度（像素/秒²），松开按键后以同样的加速度停下
+pub const PHYSICS_STEP: f64 = 1.0 / 120.0; // 物理模拟的固定步长（秒），与显示器刷新率无关
+const MAX_FRAME_TIME: f64 = 0.25; // 单帧最多补偿的时间，防止切换标签页后一次性模拟过多步
+pub const DEFAULT_STARTING_LIVES: u32 = 3; // 默认初始生命数
+const EXPLOSION_RADIUS: f64 = 60.0; // 爆炸砖块波及的范围（砖块中心之间的距离）
+const MAX_BALLS: usize = 12; // 分裂道具最多让场上同时存在的球数
+const SPLIT_ANGLE: f64 = 0.35; // 分裂出的球相对原来方向偏转的角度（弧度）
+const MAX_CONTACTS_PER_STEP: usize = 8; // 每个物理步内最多处理的碰撞次数，防止在夹缝中无限反弹
+const CONTACT_SKIN: f64 = 0.01; // 碰撞后把球沿法线推离接触面的距离，避免浮点误差让球陷进物体
+const SIMULTANEOUS_HIT_TIME: f64 = 1e-6; // 碰撞时间相差不超过这个值（秒）的砖块视为同时碰到
+const SPIN_TRANSFER: f64 = 0.02; // 球碰到挡板时，挡板每1像素/秒的速度给球增加的旋转速度（弧度/秒）
+const PADDLE_MOTION_SMOOTHING: f64 = 0.3; // 挡板速度估计的平滑系数，鼠标事件与物理步不同步，逐步测得的位移忽大忽小
+
+// 游戏所处的阶段
+//
+// 每个阶段在update和render中都有自己的处理函数，
+// 阶段之间只通过handle_input和物理模拟中的事件切换
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Phase {
+    Title,        // 标题界面，等待开始新游戏
+    Serving,      // 球停在挡板上，等待发球
+    Playing,      // 球在场上运动
+    Paused,       // 暂停，画面冻结
+    LevelCleared, // 本关砖块全部清除，等待进入下一关
+    GameOver,     // 生命用完
+}
+
+impl Phase {
+    // 只有等待发球和游戏中这两个阶段需要推进物理步
+    fn is_simulated(self) -> bool {
+        matches!(self, Phase::Serving | Phase::Playing)
+    }
+}
+
+// 玩家输入
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Input {
+    Click,       // 点击画布：开始、发球、继续
+    TogglePause, // 暂停或继续
+    Pause,       // 暂停（已暂停时无效）
+    Resume,      // 继续（未暂停时无效）
+}
+
+// 游戏状态
+//
+// 所有时间相关的方法都接收外部传入的时间戳（毫秒），
+// 不直接访问浏览器API，因此可以脱离浏览器运行和测试
+pub struct GameState {
+    pub config: GameConfig, // 场地和物体的尺寸
+    pub balls: Vec<Ball>, // 场上所有的球，最后一个球掉出底部才失去生命
+    pub paddle_x: f64,
+    pub paddle_direction: f64, // 挡板的移动方向，-1向左，1向右，0停止，由键盘和手柄的方向合并得到
+    pub keyboard_direction: f64, // 键盘按住的方向
+    pub gamepad_direction: f64,  // 手柄摇杆或十字键的方向
+    pub paddle_velocity: f64,  // 键盘控制时挡板当前的速度（像素/秒）
+    pub paddle_motion: f64,    // 由相邻物理步之间的位移估计出的挡板速度（像素/秒），鼠标和键盘的移动都计算在内
+    pub tracked_paddle_x: f64, // 上一个物理步开始时挡板的位置
+    pub bricks: Vec<Brick>,
+    pub phase: Phase,
+    pub last_time: f64,  // 添加上一帧的时间戳
+    pub accumulator: f64, // 尚未被物理步消耗的时间（秒）
+    pub score: u32,          // 当前得分
+    pub lives: u32,          // 剩余生命
+    pub level: u32,          // 当前关卡，从1开始，重置砖块时保留
+    pub starting_lives: u32, // 每局开始时的生命数
+    pub custom_level: Option<Level>, // 从JS加载的关卡，为None时随机生成砖块
+    pub capsules: Vec<Capsule>,      // 正在下落的道具胶囊
+    pub lasers: Vec<LaserBolt>,      // 正在上升的激光
+    pub effects: Vec<ActiveEffect>,  // 正在生效的限时效果
+    pub laser_cooldown: f64,         // 距离下一次可以发射激光的时间（秒）
+    pub seed: u32,                   // 随机数种子，每局开始时用它重新初始化rng
+    pub rng: Pcg32,                  // 砖块布局、发球角度和道具掉落共用的随机数生成器，算法固定，相同种子在任何平台上都得到相同的一局
+    pub recorder: Option<Recorder>,  // 本局的录像，每局开始时重新录制
+    pub playback: Option<Playback>,  // 正在回放的录像，回放时忽略玩家的操作
+    pub high_scores: HighScoreTable, // 排行榜，默认只保存在内存中
+    pub initials_entry: Option<String>, // 成绩进入排行榜时正在输入的名字缩写
+    pub last_initials: String,       // 上一次输入的名字缩写，下次输入时预先填好
+    pub new_high_score: Option<usize>, // 本局成绩在排行榜中的名次，用于高亮显示
+    pub time_origin: f64,            // 时间戳为0时对应的Unix时间（毫秒），用于记录成绩的日期
+    pub events: Vec<GameEvent>,      // 尚未转发给JS的游戏事件
+    pub sounds: Vec<Sound>,          // 尚未播放的音效
+    pub debris: ParticleSystem,      // 砖块碎裂时飞出的碎片
+}
+
+pub struct Brick {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub active: bool,
+    pub points: u32, // 击碎该砖块获得的分数
+    pub kind: BrickKind,
+    pub hits_left: u32, // 还需要被击中几次才会碎
+    pub max_hits: u32,  // 初始耐久，用于绘制损坏程度
+}
+
+// 砖块种类
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BrickKind {
+    Normal,      // 普通砖块，耐久可以大于1
+    Unbreakable, // 无法击碎，不计入过关条件
+    Explosive,   // 碎裂时摧毁周围的砖块
+}
+
+impl Brick {
+    pub fn rect(&self) -> Rect {
+        Rect { x: self.x, y: self.y, width: self.width, height: self.height }
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    // 砖块的填充颜色，普通砖块耐久越低颜色越浅
+    pub fn color(&self) -> String {
+        match self.kind {
+            BrickKind::Unbreakable => "#888888".to_string(),
+            BrickKind::Explosive => "#c0392b".to_string(),
+            BrickKind::Normal => {
+                // 刚被击碎的砖块保持碎裂前最后显示的颜色
+                let damage = 1.0 - self.hits_left.max(1) as f64 / self.max_hits as f64;
+                let shade = (damage * 140.0) as u8;
+                format!("rgb({}, {}, {})", shade, shade, shade)
+            }
+        }
+    }
+
+    pub fn is_breakable(&self) -> bool {
+        self.kind != BrickKind::Unbreakable
+    }
+}
+
+// 球在一个物理步内可能碰到的物体
+enum Collider {
+    Wall,
+    Paddle,
+    Bricks(Vec<usize>), // 同时碰到的一块或多块砖，例如正好打在两块相邻砖块的接缝上
+}
+
+// 初始化游戏状态
+impl GameState {
+    pub fn new(config: GameConfig, starting_lives: u32, seed: u32, now: f64) -> Self {
+        let mut rng = Pcg32::seed_from_u64(seed as u64);
+
+        // 挡板初始位置
+        let paddle_x = (config.canvas_width - config.paddle_width) / 2.0;
+
+        // 球的初始位置在挡板上方中央
+        let ball_x = paddle_x + config.paddle_width / 2.0;
+        let ball_y = config.paddle_y() - config.ball_radius;
+
+        GameState {
+            config,
+            balls: vec![Ball::at(ball_x, ball_y)],
+            paddle_x,
+            paddle_direction: 0.0,
+            keyboard_direction: 0.0,
+            gamepad_direction: 0.0,
+            paddle_velocity: 0.0,
+            paddle_motion: 0.0,
+            tracked_paddle_x: paddle_x,
+            bricks: create_bricks(&config, &mut rng),
+            phase: Phase::Title,
+            last_time: now,
+            accumulator: 0.0,
+            score: 0,
+            lives: starting_lives,
+            level: 1,
+            starting_lives,
+            custom_level: None,
+            capsules: Vec::new(),
+            lasers: Vec::new(),
+            effects: Vec::new(),
+            laser_cooldown: 0.0,
+            seed,
+            rng,
+            recorder: None,
+            playback: None,
+            high_scores: HighScoreTable::load(Box::new(MemoryStorage::default())),
+            initials_entry: None,
+            last_initials: String::new(),
+            new_high_score: None,
+            time_origin: 0.0,
+            events: Vec::new(),
+            sounds: Vec::new(),
+            debris: ParticleSystem::new(seed),
+        }
+    }
+
+    // 开始新的一局：分数、生命和关卡回到初始值，随机数从种子重新开始，相同的种子和操作得到相同的一局
+    fn start_new_game(&mut self, now: f64) {
+        // 回放时使用录像的种子和生命数
+        let (seed, starting_lives) = match &self.playback {
+            Some(playback) => (playback.seed, playback.starting_lives),
+            None => (self.seed, self.starting_lives),
+        };
+        self.rng = Pcg32::seed_from_u64(seed as u64);
+        self.debris = ParticleSystem::new(seed);
+        self.paddle_velocity = 0.0;
+        self.paddle_motion = 0.0;
+        self.tracked_paddle_x = self.paddle_x;
+        if self.playback.is_none() {
+            self.recorder = Some(Recorder::new(self.seed, self.starting_lives));
+        }
+        self.initials_entry = None;
+        self.new_high_score = None;
+        self.score = 0;
+        self.lives = starting_lives;
+        self.level = 1;
+        self.reset(now);
+        self.emit(GameEventKind::GameStarted);
+    }
+
+    // 重置砖块并重新发球，分数、生命和关卡保持不变
+    pub fn reset(&mut self, now: f64) {
+        self.bricks = self.layout_bricks();
+        self.serve(now);
+    }
+
+    // 按当前关卡布局生成砖块：有加载的关卡就使用它，否则随机生成
+    fn layout_bricks(&mut self) -> Vec<Brick> {
+        match &self.custom_level {
+            Some(level) => level.create_bricks(),
+            None => create_bricks(&self.config, &mut self.rng),
+        }
+    }
+
+    // 设置随机数种子，从下一局开始生效
+    pub fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
+    }
+
+    // 用指定的种子立即开始新的一局
+    pub fn start_with_seed(&mut self, seed: u32, now: f64) {
+        self.seed = seed;
+        // 正在回放时停止回放，新的一局由玩家自己操作并重新录像
+        self.playback = None;
+        self.start_new_game(now);
+    }
+
+    // 加载关卡布局，之后每一关都使用该布局；正在进行的一局会立即换上新的砖块并重新发球
+    pub fn load_level(&mut self, level: Level, now: f64) {
+        self.custom_level = Some(level);
+        self.bricks = self.layout_bricks();
+
+        if !matches!(self.phase, Phase::Title | Phase::GameOver) {
+            // 中途换了砖块，这一局的录像已经无法重现
+            self.recorder = None;
+            self.playback = None;
+            self.serve(now);
+        }
+    }
+
+    // 回放录像：用录像的种子和生命数开始新的一局，之后按录下的输入自动进行；玩家设置的种子和生命数保持不变
+    pub fn start_playback(&mut self, replay: Replay, now: f64) {
+        self.playback = Some(Playback::new(replay));
+        // 回放不是新的一局，丢弃上一局的录像，避免回放的物理步被追加进去
+        self.recorder = None;
+        self.start_new_game(now);
+    }
+
+    // 停止回放，玩家从当前状态接着玩
+    pub fn stop_playback(&mut self) {
+        self.playback = None;
+    }
+
+    // 把球放回挡板上方，等待玩家点击发球；所有道具效果随之结束
+    fn serve(&mut self, now: f64) {
+        self.capsules.clear();
+        self.lasers.clear();
+        self.effects.clear();
+        self.laser_cooldown = 0.0;
+        self.clamp_paddle();
+
+        let ball_x = self.paddle_x + self.paddle_width() / 2.0;
+        self.balls = vec![Ball::at(ball_x, self.config.paddle_y() - self.config.ball_radius)];
+        self.phase = Phase::Serving;
+
+        // 更新时间戳
+        self.last_time = now;
+        self.accumulator = 0.0;
+    }
+
+    // 发球：给球一个随机方向
+    fn launch(&mut self, now: f64) {
+        let angle = self.rng.gen_range((-60.0_f64).to_radians()..(60.0_f64).to_radians());
+        let speed = speed_range(self.level).min;
+
+        for ball in &mut self.balls {
+            ball.dx = speed * angle.sin(); // 使用sin来计算水平速度
+            ball.dy = -speed * angle.cos(); // 使用cos来确保主要是垂直运动
+        }
+        self.phase = Phase::Playing;
+
+        // 重置时间戳，确保第一帧的delta time合理
+        self.last_time = now;
+        self.accumulator = 0.0;
+    }
+
+    // 修改每局开始时的生命数；如果当前这局还没开始，立即生效
+    pub fn set_starting_lives(&mut self, lives: u32) {
+        self.starting_lives = lives.max(1);
+
+        let fresh_game = self.phase == Phase::Serving && self.score == 0 && self.level == 1;
+        if self.phase == Phase::Title || fresh_game {
+            self.lives = self.starting_lives;
+        }
+    }
+
+    // 设置挡板位置（逻辑坐标下挡板中心的x），并限制在画布范围内
+    pub fn move_paddle_to(&mut self, center_x: f64) {
+        // 暂停时画面冻结，挡板也不动；回放时挡板由录像控制
+        if self.phase == Phase::Paused || self.playback.is_some() {
+            return;
+        }
+
+        self.paddle_x = center_x - self.paddle_width() / 2.0;
+        self.clamp_paddle();
+    }
+
+    // 设置键盘控制的挡板移动方向，-1向左，1向右，0松开
+    pub fn set_paddle_direction(&mut self, direction: f64) {
+        self.keyboard_direction = direction.clamp(-1.0, 1.0);
+        self.combine_directions();
+    }
+
+    // 设置手柄控制的挡板移动方向，-1向左，1向右，0居中
+    pub fn set_gamepad_direction(&mut self, direction: f64) {
+        self.gamepad_direction = direction.clamp(-1.0, 1.0);
+        self.combine_directions();
+    }
+
+    // 键盘和手柄同时有方向时以键盘为准，一方松开后另一方仍然有效
+    fn combine_directions(&mut self) {
+        if self.playback.is_some() {
+            return;
+        }
+        self.paddle_direction = if self.keyboard_direction != 0.0 { self.keyboard_direction } else { self.gamepad_direction };
+    }
+
+    // 按键盘或手柄的方向加速或减速挡板，并移动delta_time秒
+    fn drive_paddle(&mut self, delta_time: f64) {
+        let target_velocity = self.paddle_direction * PADDLE_MAX_SPEED;
+        let max_change = PADDLE_ACCELERATION * delta_time;
+        self.paddle_velocity += (target_velocity - self.paddle_velocity).clamp(-max_change, max_change);
+
+        if self.paddle_velocity != 0.0 {
+            self.paddle_x += self.paddle_velocity * delta_time;
+            // 撞到画布边缘时停下
+            if self.clamp_paddle() {
+                self.paddle_velocity = 0.0;
+            }
+        }
+    }
+
+    // 确保挡板不超出画布，返回挡板是否被挡住
+    fn clamp_paddle(&mut self) -> bool {
+        let paddle_width = self.paddle_width();
+
+        if self.paddle_x < 0.0 {
+            self.paddle_x = 0.0;
+        } else if self.paddle_x + paddle_width > self.config.canvas_width {
+            self.paddle_x = self.config.canvas_width - paddle_width;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    // 挡板占据的矩形
+    fn paddle_rect(&self) -> Rect {
+        Rect { x: self.paddle_x, y: self.config.paddle_y(), width: self.paddle_width(), height: self.config.paddle_height }
+    }
+
+    // 当前挡板宽度，受变宽和变窄道具影响
+    pub fn paddle_width(&self) -> f64 {
+        if self.has_effect(PowerUpKind::Wide) {
+            self.config.paddle_width * WIDE_PADDLE_SCALE
+        } else if self.has_effect(PowerUpKind::Narrow) {
+            self.config.paddle_width * NARROW_PADDLE_SCALE
+        } else {
+            self.config.paddle_width
+        }
+    }
+
+    pub fn has_effect(&self, kind: PowerUpKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    // 根据玩家输入切换阶段
+    pub fn handle_input(&mut self, input: Input, now: f64) {
+        // 回放时玩家只能暂停和继续
+        if self.playback.is_some() && input == Input::Click && self.phase != Phase::Paused {
+            return;
+        }
+
+        // 只有改变模拟的点击需要录下来，开始新游戏、继续和返回标题不影响这一局的进程
+        if input == Input::Click && matches!(self.phase, Phase::Serving | Phase::Playing | Phase::LevelCleared) {
+            if let Some(recorder) = &mut self.recorder {
+                recorder.click(self.paddle_x, self.paddle_direction);
+            }
+        }
+
+        self.apply_input(input, now);
+    }
+
+    fn apply_input(&mut self, input: Input, now: f64) {
+        match (self.phase, input) {
+            (Phase::Title, Input::Click) => self.start_new_game(now),
+            (Phase::Serving, Input::Click) => self.launch(now),
+            (Phase::Playing, Input::Click) => self.release_or_fire(),
+            (Phase::Playing, Input::TogglePause | Input::Pause) => self.phase = Phase::Paused,
+            (Phase::Paused, Input::Click | Input::TogglePause | Input::Resume) => {
+                // 从暂停恢复时重置时间戳，暂停期间的时间不计入模拟
+                self.last_time = now;
+                self.phase = Phase::Playing;
+            }
+            (Phase::LevelCleared, Input::Click) => {
+                self.level += 1;
+                self.reset(now);
+            }
+            (Phase::GameOver, Input::Click) => match self.initials_entry.take() {
+                Some(initials) => self.submit_high_score(initials, now),
+                None => self.phase = Phase::Title,
+            },
+            _ => {}
+        }
+    }
+
+    // 本关还需要击碎的砖块数，不可摧毁的砖块不计入
+    pub fn remaining_bricks(&self) -> usize {
+        self.bricks.iter().filter(|brick| brick.active && brick.is_breakable()).count()
+    }
+
+    // 场上最上面TOP_ROWS行砖块的底边，按还没碎的砖块计算，关卡布局不同或上面几行被打掉后跟着下移；
+    // 场上没有砖块时返回None
+    fn top_rows_bottom(&self) -> Option<f64> {
+        let mut rows: Vec<f64> = self.bricks.iter().filter(|brick| brick.active).map(|brick| brick.y).collect();
+        rows.sort_by(f64::total_cmp);
+        rows.dedup();
+        rows.truncate(TOP_ROWS);
+        let last_row = *rows.last()?;
+
+        self.bricks
+            .iter()
+            .filter(|brick| brick.active && brick.y <= last_row)
+            .map(|brick| brick.y + brick.height)
+            .reduce(f64::max)
+    }
+
+    // 记下一个游戏事件，附带事件发生后的分数、关卡、生命和剩余砖块数
+    fn emit(&mut self, kind: GameEventKind) {
+        let event = GameEvent {
+            kind,
+            score: self.score,
+            level: self.level,
+            lives: self.lives,
+            remaining_bricks: self.remaining_bricks(),
+        };
+        self.events.push(event);
+    }
+
+    // 是否正在输入名字缩写
+    pub fn is_entering_initials(&self) -> bool {
+        self.initials_entry.is_some()
+    }
+
+    // 输入名字缩写的一个字符，只接受字母和数字
+    pub fn type_initial(&mut self, c: char) {
+        if let Some(initials) = &mut self.initials_entry {
+            if c.is_ascii_alphanumeric() && initials.len() < MAX_INITIALS {
+                initials.push(c.to_ascii_uppercase());
+            }
+        }
+    }
+
+    // 删除名字缩写的最后一个字符
+    pub fn erase_initial(&mut self) {
+        if let Some(initials) = &mut self.initials_entry {
+            initials.pop();
+        }
+    }
+
+    // 生命用完：成绩能进入排行榜时先让玩家输入名字缩写，回放的成绩不计入排行榜
+    fn end_game(&mut self) {
+        self.phase = Phase::GameOver;
+        self.emit(GameEventKind::GameOver);
+        if self.playback.is_none() && self.high_scores.qualifies(self.score) {
+            self.initials_entry = Some(self.last_initials.clone());
+        }
+    }
+
+    fn submit_high_score(&mut self, initials: String, now: f64) {
+        let initials = if initials.is_empty() { "???".to_string() } else { initials };
+        self.last_initials = initials.clone();
+        self.new_high_score = self.high_scores.submit(HighScore {
+            initials,
+            score: self.score,
+            level: self.level,
+            date: format_date(self.time_origin + now),
+        });
+    }
+
+    // 更新游戏状态，now为当前时间戳（毫秒）
+    pub fn update(&mut self, now: f64) {
+        // 计算时间差（delta time）
+        let delta_time = (now - self.last_time) / 1000.0; // 转换为秒
+        self.last_time = now;
+
+        // 限制单帧时间，防止在切换标签页等情况下一次性补偿过多物理步
+        let delta_time = delta_time.clamp(0.0, MAX_FRAME_TIME);
+
+        // 碎片只是画面效果，除了暂停以外一直运动，过关和游戏结束的画面上也能看到它们落下
+        if self.phase != Phase::Paused {
+            self.debris.update(delta_time);
+        }
+
+        if self.playback.is_some() && self.phase != Phase::Paused {
+            self.update_playback(delta_time);
+            return;
+        }
+
+        // 静止的阶段只记录时间，避免切换回来时出现很大的delta time
+        if !self.phase.is_simulated() {
+            return;
+        }
+
+        // 以固定步长推进物理模拟，剩余不足一步的时间留给下一帧
+        self.accumulator += delta_time;
+        while self.accumulator >= PHYSICS_STEP {
+            self.accumulator -= PHYSICS_STEP;
+            self.tick();
+
+            // 失去最后一条生命或清空砖块后不再继续模拟
+            if !self.phase.is_simulated() {
+                self.accumulator = 0.0;
+                break;
+            }
+        }
+    }
+
+    // 回放：依次应用录下的输入，每个物理步消耗录像中的一个物理步
+    fn update_playback(&mut self, delta_time: f64) {
+        self.accumulator += delta_time;
+
+        loop {
+            // 先应用下一个物理步之前的所有输入
+            while let Some(record) = self.playback.as_mut().and_then(Playback::next_input) {
+                match record {
+                    Record::Paddle(x) => self.paddle_x = x,
+                    Record::Direction(direction) => self.paddle_direction = direction,
+                    Record::Click => self.apply_input(Input::Click, self.last_time),
+                    Record::Ticks(_) => {}
+                }
+            }
+
+            let Some(playback) = &mut self.playback else {
+                break;
+            };
+            if playback.is_finished() {
+                // 放完了，玩家接着玩
+                self.playback = None;
+                break;
+            }
+            if self.accumulator < PHYSICS_STEP {
+                break;
+            }
+            // 录像与当前状态对不上（例如场地配置不同）时停止回放
+            if !self.phase.is_simulated() || !playback.take_tick() {
+                self.playback = None;
+                break;
+            }
+
+            self.accumulator -= PHYSICS_STEP;
+            self.tick();
+        }
+    }
+
+    // 推进一个物理步：等待发球时只有挡板移动，游戏中进行完整的物理模拟
+    fn tick(&mut self) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.tick(self.paddle_x, self.paddle_direction);
+        }
+        self.track_paddle();
+
+        match self.phase {
+            Phase::Serving => self.step_serving(PHYSICS_STEP),
+            Phase::Playing => self.step(PHYSICS_STEP),
+            _ => {}
+        }
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.settle(self.paddle_x);
+        }
+    }
+
+    // 用上一个物理步以来挡板的位移更新挡板速度的估计
+    fn track_paddle(&mut self) {
+        let measured = (self.paddle_x - self.tracked_paddle_x) / PHYSICS_STEP;
+        self.tracked_paddle_x = self.paddle_x;
+        self.paddle_motion += (measured - self.paddle_motion) * PADDLE_MOTION_SMOOTHING;
+    }
+
+    // 游戏中点击：有球粘在挡板上时发射这些球，否则在有激光时发射激光
+    fn release_or_fire(&mut self) {
+        let mut released = false;
+        for index in 0..self.balls.len() {
+            if self.balls[index].stuck.take().is_some() {
+                let mut ball = self.balls[index];
+                self.bounce_off_paddle_top(&mut ball);
+                self.balls[index] = ball;
+                released = true;
+            }
+        }
+        if released {
+            return;
+        }
+
+        if self.has_effect(PowerUpKind::Laser) && self.laser_cooldown <= 0.0 {
+            // 从挡板两端各射出一道激光
+            let top = self.config.paddle_y() - LASER_LENGTH;
+            self.lasers.push(LaserBolt { x: self.paddle_x + 4.0, y: top });
+            self.lasers.push(LaserBolt { x: self.paddle_x + self.paddle_width() - 4.0, y: top });
+            self.laser_cooldown = LASER_COOLDOWN;
+        }
+    }
+
+    // 等待发球时球跟随挡板移动
+    fn step_serving(&mut self, delta_time: f64) {
+        self.drive_paddle(delta_time);
+
+        let ball_x = self.paddle_x + self.paddle_width() / 2.0;
+        let ball_y = self.config.paddle_y() - self.config.ball_radius;
+        for ball in &mut self.balls {
+            ball.place(ball_x, ball_y);
+        }
+    }
+
+    // 推进一个固定步长的物理模拟
+    fn step(&mut self, delta_time: f64) {
+        // 道具计时，挡板宽度可能因此变化
+        tick_effects(&mut self.effects, delta_time);
+        self.clamp_paddle();
+        self.laser_cooldown = (self.laser_cooldown - delta_time).max(0.0);
+        self.drive_paddle(delta_time);
+
+        self.update_capsules(delta_time);
+        self.update_lasers(delta_time);
+
+        // 每个球分别运动和碰撞
+        let ball_time = if self.has_effect(PowerUpKind::Slow) { delta_time * SLOW_BALL_SCALE } else { delta_time };
+        let max_speed = speed_range(self.level).max;
+        let top_rows_bottom = self.top_rows_bottom();
+        let mut balls = std::mem::take(&mut self.balls);
+        for ball in &mut balls {
+            // 记录上一步的位置，用于渲染插值
+            ball.prev_x = ball.x;
+            ball.prev_y = ball.y;
+
+            match ball.stuck {
+                // 粘在挡板上的球跟随挡板移动
+                Some(offset) => {
+                    ball.x = self.paddle_x + offset.clamp(0.0, self.paddle_width());
+                    ball.y = self.config.paddle_y() - self.config.ball_radius;
+                }
+                None => {
+                    ball.speed_up(SPEEDUP_PER_SECOND * delta_time, max_speed);
+                    ball.apply_spin(ball_time);
+                    self.move_ball(ball, ball_time);
+
+                    // 第一次到达顶部几行砖块时加速
+                    if !ball.reached_top && top_rows_bottom.is_some_and(|bottom| ball.y - self.config.ball_radius < bottom) {
+                        ball.reached_top = true;
+                        ball.speed_up(TOP_ROWS_SPEEDUP, max_speed);
+                    }
+                }
+            }
+        }
+
+        // 掉出底部的球被移除，最后一个球掉出时失去一条生命，生命用完则游戏结束
+        let (ball_radius, canvas_height) = (self.config.ball_radius, self.config.canvas_height);
+        balls.retain(|ball| ball.y + ball_radius < canvas_height);
+        self.balls = balls;
+        if self.balls.is_empty() {
+            self.lives = self.lives.saturating_sub(1);
+            self.emit(GameEventKind::LifeLost);
+            self.sounds.push(Sound::LifeLost);
+            if self.lives == 0 {
+                self.end_game();
+            } else {
+                self.serve(self.last_time);
+            }
+            return;
+        }
+
+        // 清空所有砖块后等待玩家进入下一关
+        if self.remaining_bricks() == 0 {
+            self.phase = Phase::LevelCleared;
+            self.emit(GameEventKind::LevelCleared);
+            self.sounds.push(Sound::LevelClear);
+        }
+    }
+
+    // 让球运动delta_time秒，并在这段时间内按时间顺序依次处理所有碰撞
+    fn move_ball(&mut self, ball: &mut Ball, delta_time: f64) {
+        self.separate_from_bricks(ball);
+        self.separate_from_paddle(ball);
+        if ball.stuck.is_some() {
+            return;
+        }
+
+        let mut remaining = delta_time;
+        for _ in 0..MAX_CONTACTS_PER_STEP {
+            let Some((hit, collider)) = self.earliest_hit(ball, remaining) else {
+                break;
+            };
+
+            // 球先移动到碰撞发生的位置，再根据碰撞对象改变方向
+            ball.x += ball.dx * hit.time + hit.normal_x * CONTACT_SKIN;
+            ball.y += ball.dy * hit.time + hit.normal_y * CONTACT_SKIN;
+            remaining -= hit.time;
+
+            match collider {
+                Collider::Wall => {
+                    (ball.dx, ball.dy) = reflect(ball.dx, ball.dy, hit.normal_x, hit.normal_y);
+                    self.sounds.push(Sound::Bounce);
+                }
+                Collider::Paddle => {
+                    self.bounce_off_paddle(ball, &hit);
+                    self.sounds.push(Sound::PaddleHit);
+                    if ball.stuck.is_some() {
+                        return;
+                    }
+                }
+                Collider::Bricks(indices) => {
+                    for &index in &indices {
+                        self.hit_brick(index);
+                    }
+                    if indices.iter().any(|&index| self.bricks[index].active) {
+                        self.sounds.push(Sound::Bounce);
+                    }
+                    // 同时碰到多块砖时只按合成的法线反弹一次
+                    if ball.dx * hit.normal_x + ball.dy * hit.normal_y < 0.0 {
+                        (ball.dx, ball.dy) = reflect(ball.dx, ball.dy, hit.normal_x, hit.normal_y);
+                    }
+                }
+            }
+        }
+
+        // 剩余时间内不再有碰撞，直接移动
+        ball.x += ball.dx * remaining;
+        ball.y += ball.dy * remaining;
+    }
+
+    // 胶囊下落，被挡板接住时获得道具，掉出底部则消失
+    fn update_capsules(&mut self, delta_time: f64) {
+        let paddle = self.paddle_rect();
+        let canvas_height = self.config.canvas_height;
+        let mut caught = Vec::new();
+
+        self.capsules.retain_mut(|capsule| {
+            capsule.update(delta_time);
+
+            let rect = capsule.rect();
+            if rect.overlaps(&paddle) {
+                caught.push(capsule.kind);
+                return false;
+            }
+            rect.y < canvas_height
+        });
+
+        for kind in caught {
+            self.apply_power_up(kind);
+        }
+    }
+
+    // 获得道具效果
+    fn apply_power_up(&mut self, kind: PowerUpKind) {
+        match kind {
+            PowerUpKind::ExtraLife => self.lives += 1,
+            PowerUpKind::MultiBall => self.split_balls(),
+            _ => add_effect(&mut self.effects, kind),
+        }
+        self.clamp_paddle();
+    }
+
+    // 每个球分裂成三个，总数不超过上限
+    fn split_balls(&mut self) {
+        let mut new_balls = Vec::new();
+        for ball in &self.balls {
+            for angle in [-SPLIT_ANGLE, SPLIT_ANGLE] {
+                if self.balls.len() + new_balls.len() < MAX_BALLS {
+                    new_balls.push(ball.split(angle));
+                }
+            }
+        }
+        self.balls.extend(new_balls);
+    }
+
+    // 激光上升，碰到砖块时击中砖块并消失
+    fn update_lasers(&mut self, delta_time: f64) {
+        let mut lasers = std::mem::take(&mut self.lasers);
+
+        lasers.retain_mut(|laser| {
+            laser.update(delta_time);
+
+            let target = self.bricks.iter().position(|brick| {
+                brick.active
+                    && laser.x >= brick.x
+                    && laser.x <= brick.x + brick.width
+                    && laser.y <= brick.y + brick.height
+                    && laser.y + LASER_LENGTH >= brick.y
+            });
+            if let Some(index) = target {
+                self.hit_brick(index);
+                return false;
+            }
+            laser.y + LASER_LENGTH > 0.0
+        });
+
+        self.lasers = lasers;
+    }
+
+    // 在max_time内找出球最早碰到的物体（墙壁、挡板或砖块）
+    fn earliest_hit(&self, ball: &Ball, max_time: f64) -> Option<(Hit, Collider)> {
+        let (x, y, dx, dy) = (ball.x, ball.y, ball.dx, ball.dy);
+        let mut earliest: Option<(Hit, Collider)> = None;
+        let mut consider = |hit: Option<Hit>, collider: Collider| {
+            if let Some(hit) = hit {
+                if earliest.as_ref().is_none_or(|(best, _)| hit.time < best.time) {
+                    earliest = Some((hit, collider));
+                }
+            }
+        };
+
+        // 左、右、上三面墙，底部是出界区域不反弹
+        let radius = self.config.ball_radius;
+        consider(sweep_vertical_line(x, dx, radius, 1.0, max_time), Collider::Wall);
+        consider(sweep_vertical_line(x, dx, self.config.canvas_width - radius, -1.0, max_time), Collider::Wall);
+        consider(sweep_horizontal_line(y, dy, radius, 1.0, max_time), Collider::Wall);
+
+        consider(sweep_circle_rect(x, y, dx, dy, radius, &self.paddle_rect(), max_time), Collider::Paddle);
+
+        // 最早碰到的砖块，以及几乎同时碰到的其他砖块；它们的法线合成为一个，
+        // 打在两块砖的接缝上时按平整的表面反弹，两块砖都算被击中
+        let brick_hits: Vec<(Hit, usize)> = self
+            .bricks
+            .iter()
+            .enumerate()
+            .filter(|(_, brick)| brick.active)
+            .filter_map(|(index, brick)| Some((sweep_circle_rect(x, y, dx, dy, radius, &brick.rect(), max_time)?, index)))
+            .collect();
+        if let Some(first) = brick_hits.iter().map(|(hit, _)| hit.time).reduce(f64::min) {
+            let touching: Vec<&(Hit, usize)> = brick_hits.iter().filter(|(hit, _)| hit.time <= first + SIMULTANEOUS_HIT_TIME).collect();
+            let normal_x: f64 = touching.iter().map(|(hit, _)| hit.normal_x).sum();
+            let normal_y: f64 = touching.iter().map(|(hit, _)| hit.normal_y).sum();
+            let length = (normal_x * normal_x + normal_y * normal_y).sqrt();
+
+            // 法线相互抵消时（几乎不会发生）退回到第一块砖的法线
+            let (normal_x, normal_y) = if length > 0.0 {
+                (normal_x / length, normal_y / length)
+            } else {
+                (touching[0].0.normal_x, touching[0].0.normal_y)
+            };
+            let hit = Hit { time: first, normal_x, normal_y };
+            consider(Some(hit), Collider::Bricks(touching.iter().map(|(_, index)| *index).collect()));
+        }
+
+        earliest
+    }
+
+    // 挡板移到球下方时球可能已经与挡板重叠，扫描检测不会再报告碰撞：
+    // 正在下落的球被放回挡板顶面并按挡板反弹，其他情况沿最短方向把球推出来
+    fn separate_from_paddle(&mut self, ball: &mut Ball) {
+        let paddle = self.paddle_rect();
+        let radius = self.config.ball_radius;
+        let Some(contact) = penetration(ball.x, ball.y, radius, &paddle) else {
+            return;
+        };
+
+        if ball.dy > 0.0 && ball.y <= paddle.y + paddle.height {
+            ball.y = paddle.y - radius - CONTACT_SKIN;
+            self.bounce_off_paddle(ball, &Hit { time: 0.0, normal_x: 0.0, normal_y: -1.0 });
+            self.sounds.push(Sound::PaddleHit);
+        } else {
+            ball.x += contact.normal_x * (contact.depth + CONTACT_SKIN);
+            ball.y += contact.normal_y * (contact.depth + CONTACT_SKIN);
+        }
+    }
+
+    // 球与砖块重叠时（例如分裂出的球正好落在砖块里），沿最短的方向把球推出来，正朝砖块运动时同时反弹
+    fn separate_from_bricks(&self, ball: &mut Ball) {
+        for brick in self.bricks.iter().filter(|brick| brick.active) {
+            let Some(contact) = penetration(ball.x, ball.y, self.config.ball_radius, &brick.rect()) else {
+                continue;
+            };
+
+            ball.x += contact.normal_x * (contact.depth + CONTACT_SKIN);
+            ball.y += contact.normal_y * (contact.depth + CONTACT_SKIN);
+            if ball.dx * contact.normal_x + ball.dy * contact.normal_y < 0.0 {
+                (ball.dx, ball.dy) = reflect(ball.dx, ball.dy, contact.normal_x, contact.normal_y);
+            }
+        }
+    }
+
+    // 球击中砖块：扣除耐久，耐久耗尽时砖块碎裂
+    fn hit_brick(&mut self, index: usize) {
+        let brick = &mut self.bricks[index];
+        // 同时击中的砖块可能已经被前一块爆炸砖块炸掉了
+        if !brick.active || !brick.is_breakable() {
+            return;
+        }
+
+        brick.hits_left = brick.hits_left.saturating_sub(1);
+        if brick.hits_left == 0 {
+            self.destroy_brick(index);
+        }
+    }
+
+    // 击碎砖块并计分；爆炸砖块会连锁摧毁范围内所有可击碎的砖块
+    fn destroy_brick(&mut self, index: usize) {
+        self.sounds.push(Sound::BrickBreak);
+        let mut pending = vec![index];
+
+        while let Some(index) = pending.pop() {
+            let brick = &mut self.bricks[index];
+            if !brick.active {
+                continue;
+            }
+
+            brick.active = false;
+            self.score += brick.points;
+            let (center_x, center_y) = brick.center();
+            self.debris.burst(brick.x, brick.y, brick.width, brick.height, &brick.color());
+            self.emit(GameEventKind::BrickDestroyed);
+
+            // 有一定概率掉落道具胶囊
+            if self.rng.gen_bool(DROP_CHANCE) {
+                self.capsules.push(Capsule { x: center_x, y: center_y, kind: PowerUpKind::random(&mut self.rng) });
+            }
+
+            if self.bricks[index].kind == BrickKind::Explosive {
+                for (other_index, other) in self.bricks.iter().enumerate() {
+                    let (other_x, other_y) = other.center();
+                    let distance = ((other_x - center_x).powi(2) + (other_y - center_y).powi(2)).sqrt();
+                    if other.active && other.is_breakable() && distance <= EXPLOSION_RADIUS {
+                        pending.push(other_index);
+                    }
+                }
+            }
+        }
+    }
+
+    // 处理球与挡板的碰撞
+    fn bounce_off_paddle(&self, ball: &mut Ball, hit: &Hit) {
+        // 碰到挡板侧面时只做普通反射
+        if hit.normal_y >= 0.0 {
+            (ball.dx, ball.dy) = reflect(ball.dx, ball.dy, hit.normal_x, hit.normal_y);
+            return;
+        }
+
+        // 粘性挡板：球停在挡板上，等待玩家点击再发射
+        if self.has_effect(PowerUpKind::Sticky) {
+            ball.stuck = Some(ball.x - self.paddle_x);
+            return;
+        }
+
+        self.bounce_off_paddle_top(ball);
+    }
+
+    // 球从挡板顶面弹起
+    fn bounce_off_paddle_top(&self, ball: &mut Ball) {
+        // 根据击中挡板的位置计算反弹角度
+        let half_width = self.paddle_width() / 2.0;
+        let relative_intersect_x = (self.paddle_x + half_width) - ball.x;
+        let normalized_intersect_x = (relative_intersect_x / half_width).clamp(-1.0, 1.0);
+        let bounce_angle = normalized_intersect_x * 60.0_f64.to_radians();
+
+        // 保持相同的速度大小，只改变方向
+        let current_speed = ball.speed();
+        // 根据当前速度重新计算dx和dy分量
+        ball.dx = current_speed * bounce_angle.sin();
+        ball.dy = -current_speed * bounce_angle.cos().abs(); // 向上反弹
+
+        ball.speed_up(PADDLE_HIT_SPEEDUP, speed_range(self.level).max);
+
+        // 挡板的水平速度让球旋转，之后的轨迹向挡板移动的方向弯曲
+        ball.spin = (ball.spin + self.paddle_motion * SPIN_TRANSFER).clamp(-MAX_SPIN, MAX_SPIN);
+    }
+
+    // 渲染插值系数：剩余的累积时间占一个物理步的比例
+    pub fn render_alpha(&self) -> f64 {
+        (self.accumulator / PHYSICS_STEP).clamp(0.0, 1.0)
+    }
+}
+
+// 按配置的网格随机创建砖块
+pub fn create_bricks(config: &GameConfig, rng: &mut impl Rng) -> Vec<Brick> {
+    let mut bricks = Vec::new();
+
+    for row in 0..config.brick_rows {
+        for col in 0..config.brick_cols {
+            // 随机确定是否创建砖块
+            if rng.gen_bool(0.8) { // 80%概率创建砖块
+                // 最上面一行需要击中两次，其余砖块有一定概率是爆炸砖块
+                let hits = if row == 0 { 2 } else { 1 };
+                let kind = if row > 0 && rng.gen_bool(0.08) { BrickKind::Explosive } else { BrickKind::Normal };
+
+                let brick = Brick {
+                    x: col as f64 * (config.brick_width + config.brick_padding) + config.brick_padding,
+                    y: row as f64 * (config.brick_height + config.brick_padding) + config.brick_top_offset,
+                    width: config.brick_width,
+                    height: config.brick_height,
+                    active: true,
+                    // 越靠上的砖块越难打到，分数越高
+                    points: ((config.brick_rows - row) * 10) as u32,
+                    kind,
+                    hits_left: hits,
+                    max_hits: hits,
+                };
+                bricks.push(brick);
+            }
+        }
+    }
+
+    bricks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f64 = 1000.0 / 60.0; // 一帧的时间（毫秒）
+
+    // 已经发球的一局，时间从0开始
+    fn playing_game() -> GameState {
+        let mut game = GameState::new(GameConfig::default(), 3, 1, 0.0);
+        game.handle_input(Input::Click, 0.0);
+        game.handle_input(Input::Click, 0.0);
+        game
+    }
+
+    fn brick_at(x: f64, y: f64) -> Brick {
+        Brick { x, y, width: 50.0, height: 20.0, active: true, points: 10, kind: BrickKind::Normal, hits_left: 1, max_hits: 1 }
+    }
+
+    // 只留一块球碰不到的砖块，避免清空砖块直接过关
+    fn with_far_brick(game: &mut GameState) {
+        game.bricks = vec![brick_at(385.0, 40.0)];
+    }
+
+    // 把唯一的球放到(x, y)，以(dx, dy)运动
+    fn set_ball(game: &mut GameState, x: f64, y: f64, dx: f64, dy: f64) {
+        let ball = &mut game.balls[0];
+        ball.place(x, y);
+        ball.dx = dx;
+        ball.dy = dy;
+    }
+
+    // 以60帧每秒推进time毫秒
+    fn run_for(game: &mut GameState, start: f64, time: f64) -> f64 {
+        let mut now = start;
+        while now < start + time {
+            now += FRAME;
+            game.update(now);
+        }
+        now
+    }
+
+    #[test]
+    fn click_serves_then_launches() {
+        let mut game = GameState::new(GameConfig::default(), 3, 1, 0.0);
+        assert_eq!(game.phase, Phase::Title);
+
+        game.handle_input(Input::Click, 0.0);
+        assert_eq!(game.phase, Phase::Serving);
+        assert_eq!(game.balls.len(), 1);
+        assert_eq!(game.balls[0].y, game.config.paddle_y() - game.config.ball_radius);
+
+        game.handle_input(Input::Click, 0.0);
+        assert_eq!(game.phase, Phase::Playing);
+        assert!(game.balls[0].dy < 0.0);
+        assert!((game.balls[0].speed() - speed_range(1).min).abs() < 1e-9);
+    }
+
+    #[test]
+    fn update_advances_in_fixed_steps() {
+        let mut game = playing_game();
+        with_far_brick(&mut game);
+        set_ball(&mut game, 200.0, 150.0, 0.0, -300.0);
+
+        // 20毫秒是两个完整的物理步，剩下的时间留在累积器里
+        game.update(20.0);
+        assert!((game.accumulator - (0.020 - 2.0 * PHYSICS_STEP)).abs() < 1e-9);
+        assert!((game.balls[0].prev_y - (150.0 - 300.0 * PHYSICS_STEP)).abs() < 0.01);
+        assert!((game.balls[0].y - (150.0 - 600.0 * PHYSICS_STEP)).abs() < 0.01);
+
+        // 时间没有前进时不推进模拟
+        let y = game.balls[0].y;
+        game.update(20.0);
+        assert_eq!(game.balls[0].y, y);
+    }
+
+    #[test]
+    fn frame_rate_does_not_change_the_simulation() {
+        let mut slow = playing_game();
+        let mut fast = playing_game();
+        let mut now = 0.0;
+        for _ in 0..60 {
+            now += 1000.0 / 30.0;
+            slow.update(now);
+        }
+        let mut now = 0.0;
+        for _ in 0..240 {
+            now += 1000.0 / 120.0;
+            fast.update(now);
+        }
+
+        assert!((slow.balls[0].x - fast.balls[0].x).abs() < 1e-6);
+        assert!((slow.balls[0].y - fast.balls[0].y).abs() < 1e-6);
+        assert_eq!(slow.score, fast.score);
+    }
+
+    #[test]
+    fn ball_breaks_brick_and_bounces() {
+        let mut game = playing_game();
+        game.bricks = vec![brick_at(175.0, 100.0), brick_at(385.0, 40.0)];
+        set_ball(&mut game, 200.0, 200.0, 0.0, -300.0);
+
+        run_for(&mut game, 0.0, 500.0);
+        assert!(!game.bricks[0].active);
+        assert_eq!(game.score, 10);
+        assert!(game.balls[0].dy > 0.0);
+        assert_eq!(game.remaining_bricks(), 1);
+    }
+
+    #[test]
+    fn keyboard_and_gamepad_directions_combine() {
+        let mut game = playing_game();
+        game.set_gamepad_direction(0.5);
+        assert_eq!(game.paddle_direction, 0.5);
+
+        // 按下键盘方向时以键盘为准，松开后回到仍然按住的摇杆
+        game.set_paddle_direction(-1.0);
+        assert_eq!(game.paddle_direction, -1.0);
+        game.set_paddle_direction(0.0);
+        assert_eq!(game.paddle_direction, 0.5);
+
+        game.set_gamepad_direction(0.0);
+        assert_eq!(game.paddle_direction, 0.0);
+    }
+
+    #[test]
+    fn bomb_destroys_breakable_neighbours_in_a_chain() {
+        let mut game = playing_game();
+        let mut bomb = brick_at(175.0, 100.0);
+        bomb.kind = BrickKind::Explosive;
+        let mut steel = brick_at(120.0, 100.0);
+        steel.kind = BrickKind::Unbreakable;
+        let mut second_bomb = brick_at(230.0, 100.0);
+        second_bomb.kind = BrickKind::Explosive;
+        // 第三块在第一块爆炸砖块的范围外，被第二块炸掉
+        game.bricks = vec![bomb, steel, second_bomb, brick_at(285.0, 100.0), brick_at(385.0, 40.0)];
+        set_ball(&mut game, 200.0, 200.0, 0.0, -300.0);
+
+        run_for(&mut game, 0.0, 500.0);
+        let active: Vec<bool> = game.bricks.iter().map(|brick| brick.active).collect();
+        assert_eq!(active, vec![false, true, false, false, true]);
+        assert_eq!(game.score, 30);
+    }
+
+    #[test]
+    fn multi_hit_brick_breaks_on_the_last_hit() {
+        let mut game = playing_game();
+        let mut brick = brick_at(175.0, 100.0);
+        brick.hits_left = 3;
+        brick.max_hits = 3;
+        game.bricks = vec![brick, brick_at(385.0, 40.0)];
+
+        game.hit_brick(0);
+        game.hit_brick(0);
+        assert!(game.bricks[0].active);
+        assert_eq!(game.bricks[0].hits_left, 1);
+        assert_eq!(game.score, 0);
+
+        game.hit_brick(0);
+        assert!(!game.bricks[0].active);
+        assert_eq!(game.score, 10);
+    }
+
+    #[test]
+    fn level_with_only_steel_left_is_cleared() {
+        let mut game = playing_game();
+        let mut steel = brick_at(5.0, 40.0);
+        steel.kind = BrickKind::Unbreakable;
+        game.bricks = vec![steel, brick_at(175.0, 100.0)];
+        assert_eq!(game.remaining_bricks(), 1);
+        set_ball(&mut game, 200.0, 200.0, 0.0, -300.0);
+
+        run_for(&mut game, 0.0, 500.0);
+        assert!(game.bricks[0].active);
+        assert_eq!(game.remaining_bricks(), 0);
+        assert_eq!(game.phase, Phase::LevelCleared);
+    }
+
+    #[test]
+    fn losing_the_last_ball_costs_a_life() {
+        let mut game = playing_game();
+        with_far_brick(&mut game);
+        game.move_paddle_to(400.0);
+        set_ball(&mut game, 50.0, 280.0, 0.0, 300.0);
+
+        run_for(&mut game, 0.0, 500.0);
+        assert_eq!(game.lives, 2);
+        assert_eq!(game.phase, Phase::Serving);
+        assert_eq!(game.balls.len(), 1);
+    }
+
+    #[test]
+    fn losing_the_final_life_ends_the_game() {
+        let mut game = playing_game();
+        with_far_brick(&mut game);
+        game.lives = 1;
+        game.move_paddle_to(400.0);
+        set_ball(&mut game, 50.0, 280.0, 0.0, 300.0);
+
+        run_for(&mut game, 0.0, 500.0);
+        assert_eq!(game.lives, 0);
+        assert_eq!(game.phase, Phase::GameOver);
+    }
+
+    #[test]
+    fn paddle_moved_onto_a_falling_ball_bounces_it() {
+        let mut game = playing_game();
+        with_far_brick(&mut game);
+
+        // 球的下半部分已经低于挡板顶面，挡板再移到球下方
+        let paddle_y = game.config.paddle_y();
+        set_ball(&mut game, 50.0, paddle_y - 4.0, 0.0, 300.0);
+        game.move_paddle_to(50.0);
+
+        game.update(FRAME);
+        assert!(game.balls[0].dy < 0.0);
+        assert!(game.balls[0].y < paddle_y - game.config.ball_radius);
+    }
+
+    #[test]
+    fn hitting_the_seam_between_two_bricks_hits_both_once() {
+        let mut game = playing_game();
+        game.bricks = vec![brick_at(100.0, 100.0), brick_at(150.0, 100.0), brick_at(385.0, 40.0)];
+        for brick in &mut game.bricks[..2] {
+            brick.hits_left = 2;
+            brick.max_hits = 2;
+        }
+        set_ball(&mut game, 150.0, 200.0, 0.0, -300.0);
+
+        run_for(&mut game, 0.0, 300.0);
+        assert_eq!(game.bricks[0].hits_left, 1);
+        assert_eq!(game.bricks[1].hits_left, 1);
+        assert!(game.balls[0].dx.abs() < 1e-9);
+        assert!(game.balls[0].dy > 0.0);
+    }
+
+    #[test]
+    fn ball_inside_a_brick_is_pushed_out() {
+        let mut game = playing_game();
+        game.bricks = vec![brick_at(100.0, 100.0), brick_at(385.0, 40.0)];
+        set_ball(&mut game, 110.0, 105.0, 0.0, 300.0);
+
+        game.update(FRAME);
+        let ball = game.balls[0];
+        assert!(penetration(ball.x, ball.y, game.config.ball_radius, &game.bricks[0].rect()).is_none());
+        assert!(ball.dy < 0.0);
+    }
+
+    #[test]
+    fn replay_reproduces_the_game_at_any_frame_rate() {
+        // 录下一局：挡板左右摆动，不时点击
+        let mut game = GameState::new(GameConfig::default(), 9, 7, 0.0);
+        game.handle_input(Input::Click, 0.0);
+        let mut now = 0.0;
+        for frame in 0..900 {
+            now += FRAME;
+            game.move_paddle_to(220.0 + (frame as f64 / 20.0).sin() * 150.0);
+            if frame % 90 == 0 {
+                game.handle_input(Input::Click, now);
+            }
+            game.update(now);
+        }
+        // 游戏结束后挡板不再被录下，比较前确认这一局还在进行
+        assert!(game.phase.is_simulated());
+        assert!(game.score > 0);
+        let text = game.recorder.as_ref().unwrap().replay.encode();
+
+        // 用不同的帧率回放，结果完全一致
+        let mut copy = GameState::new(GameConfig::default(), 9, 99, 0.0);
+        copy.start_playback(Replay::decode(&text).unwrap(), 0.0);
+        let mut now = 0.0;
+        while copy.playback.is_some() {
+            now += 1000.0 / 144.0;
+            copy.update(now);
+        }
+
+        assert_eq!(copy.score, game.score);
+        assert_eq!(copy.lives, game.lives);
+        assert_eq!(copy.paddle_x, game.paddle_x);
+        assert_eq!(copy.balls.len(), game.balls.len());
+        for (played, recorded) in copy.balls.iter().zip(&game.balls) {
+            assert_eq!((played.x, played.y), (recorded.x, recorded.y));
+        }
+    }
+
+    #[test]
+    fn playback_does_not_extend_the_previous_recording() {
+        let mut game = playing_game();
+        run_for(&mut game, 0.0, 500.0);
+        let replay = game.recorder.as_ref().unwrap().replay.clone();
+
+        game.start_playback(replay, 500.0);
+        run_for(&mut game, 500.0, 500.0);
+        assert!(game.recorder.is_none());
+    }
+
+    #[test]
+    fn starting_a_seeded_game_stops_playback() {
+        let mut game = playing_game();
+        game.start_playback(Replay::new(7, 3), 0.0);
+        game.start_with_seed(20240501, 0.0);
+        assert!(game.playback.is_none());
+        assert!(game.recorder.is_some());
+    }
+
+    #[test]
+    fn playback_keeps_the_players_settings() {
+        let mut game = GameState::new(GameConfig::default(), 5, 42, 0.0);
+        game.start_playback(Replay::new(7, 1), 0.0);
+        assert_eq!(game.lives, 1);
+
+        game.stop_playback();
+        assert_eq!((game.seed, game.starting_lives), (42, 5));
+        game.start_with_seed(42, 0.0);
+        assert_eq!(game.lives, 5);
+    }
+
+    #[test]
+    fn simulation_queues_sounds() {
+        // 球撞上砖块
+        let mut game = playing_game();
+        game.bricks = vec![brick_at(175.0, 100.0), brick_at(385.0, 40.0)];
+        set_ball(&mut game, 200.0, 200.0, 0.0, -300.0);
+        game.sounds.clear();
+        run_for(&mut game, 0.0, 500.0);
+        assert_eq!(game.sounds, vec![Sound::BrickBreak]);
+
+        // 球碰到左墙
+        set_ball(&mut game, 20.0, 250.0, -300.0, 0.0);
+        game.sounds.clear();
+        run_for(&mut game, 500.0, 100.0);
+        assert_eq!(game.sounds, vec![Sound::Bounce]);
+
+        // 球掉出底部
+        game.move_paddle_to(400.0);
+        set_ball(&mut game, 50.0, 280.0, 0.0, 300.0);
+        game.sounds.clear();
+        run_for(&mut game, 600.0, 500.0);
+        assert_eq!(game.sounds, vec![Sound::LifeLost]);
+    }
+
+    #[test]
+    fn simulation_emits_events_with_the_game_state() {
+        let event = |kind, score, lives, remaining_bricks| GameEvent { kind, score, level: 1, lives, remaining_bricks };
+
+        // 球撞碎砖块
+        let mut game = playing_game();
+        game.bricks = vec![brick_at(175.0, 100.0), brick_at(385.0, 40.0)];
+        game.lives = 2;
+        set_ball(&mut game, 200.0, 200.0, 0.0, -300.0);
+        game.events.clear();
+        run_for(&mut game, 0.0, 500.0);
+        assert_eq!(game.events, vec![event(GameEventKind::BrickDestroyed, 10, 2, 1)]);
+
+        // 球掉出底部
+        game.move_paddle_to(400.0);
+        set_ball(&mut game, 50.0, 280.0, 0.0, 300.0);
+        game.events.clear();
+        run_for(&mut game, 500.0, 500.0);
+        assert_eq!(game.events, vec![event(GameEventKind::LifeLost, 10, 1, 1)]);
+
+        // 失去最后一条生命
+        game.handle_input(Input::Click, 1000.0);
+        set_ball(&mut game, 50.0, 280.0, 0.0, 300.0);
+        game.events.clear();
+        run_for(&mut game, 1000.0, 500.0);
+        assert_eq!(
+            game.events,
+            vec![event(GameEventKind::LifeLost, 10, 0, 1), event(GameEventKind::GameOver, 10, 0, 1)]
+        );
+    }
+
+    #[test]
+    fn top_rows_follow_the_remaining_bricks() {
+        let mut game = playing_game();
+        game.bricks = vec![brick_at(100.0, 40.0), brick_at(200.0, 40.0), brick_at(100.0, 65.0), brick_at(100.0, 90.0)];
+        assert_eq!(game.top_rows_bottom(), Some(85.0));
+
+        // 最上面一行打完后，下面一行成为新的顶部
+        game.bricks[0].active = false;
+        game.bricks[1].active = false;
+        assert_eq!(game.top_rows_bottom(), Some(110.0));
+
+        game.bricks.clear();
+        assert_eq!(game.top_rows_bottom(), None);
+    }
+}
@@ -0,0 +1,115 @@
+// 手柄输入
+//
+// Gamepad API不提供按键事件，需要在每一帧轮询所有已连接的手柄。
+// 手柄随时可能插入或拔出，所以每次轮询都重新读取手柄列表；
+// 拔出手柄后它按住的方向和按钮随之松开
+use wasm_bindgen::JsCast;
+use web_sys::{window, Gamepad, GamepadButton};
+
+use crate::game::{GameState, Input};
+
+pub const DEFAULT_DEAD_ZONE: f64 = 0.15; // 摇杆偏移不超过这个值时视为居中
+const MAX_DEAD_ZONE: f64 = 0.95;
+
+// 标准布局中摇杆和按钮的编号
+const AXIS_LEFT_STICK_X: u32 = 0;
+const BUTTON_A: u32 = 0; // 下方的面键：发球
+const BUTTON_B: u32 = 1; // 右侧的面键：发球
+const BUTTON_Y: u32 = 3; // 上方的面键：暂停
+const BUTTON_START: u32 = 9; // 暂停
+const BUTTON_DPAD_LEFT: u32 = 14;
+const BUTTON_DPAD_RIGHT: u32 = 15;
+
+// 一次轮询中所有手柄合并后的状态
+#[derive(Clone, Copy, Default)]
+struct PadState {
+    direction: f64, // 挡板移动方向，-1到1
+    launch: bool,
+    pause: bool,
+}
+
+pub struct GamepadInput {
+    dead_zone: f64,
+    previous: PadState, // 上一次轮询的状态，按钮只在按下的瞬间触发
+}
+
+impl Default for GamepadInput {
+    fn default() -> Self {
+        GamepadInput { dead_zone: DEFAULT_DEAD_ZONE, previous: PadState::default() }
+    }
+}
+
+impl GamepadInput {
+    pub fn set_dead_zone(&mut self, dead_zone: f64) {
+        self.dead_zone = dead_zone.clamp(0.0, MAX_DEAD_ZONE);
+    }
+
+    // 读取所有手柄，把变化转换成游戏输入
+    pub fn poll(&mut self, game_state: &mut GameState, now: f64) {
+        let state = self.read();
+
+        // 手柄的方向与键盘的方向分开保存，由GameState合并
+        if state.direction != self.previous.direction {
+            game_state.set_gamepad_direction(state.direction);
+        }
+        if state.launch && !self.previous.launch {
+            game_state.handle_input(Input::Click, now);
+        }
+        if state.pause && !self.previous.pause {
+            game_state.handle_input(Input::TogglePause, now);
+        }
+
+        self.previous = state;
+    }
+
+    fn read(&self) -> PadState {
+        let mut state = PadState::default();
+        let Ok(gamepads) = window().unwrap().navigator().get_gamepads() else {
+            return state;
+        };
+
+        for gamepad in gamepads.iter() {
+            // 空出来的位置是null
+            let Ok(gamepad) = gamepad.dyn_into::<Gamepad>() else {
+                continue;
+            };
+            if !gamepad.connected() {
+                continue;
+            }
+
+            let buttons = gamepad.buttons();
+            let pressed = |index: u32| {
+                buttons.get(index).dyn_into::<GamepadButton>().map(|button| button.pressed()).unwrap_or(false)
+            };
+
+            // 十字键优先于摇杆
+            let stick_x = gamepad.axes().get(AXIS_LEFT_STICK_X).as_f64().unwrap_or(0.0);
+            let direction = if pressed(BUTTON_DPAD_LEFT) {
+                -1.0
+            } else if pressed(BUTTON_DPAD_RIGHT) {
+                1.0
+            } else {
+                apply_dead_zone(stick_x, self.dead_zone)
+            };
+
+            // 多个手柄同时操作时取偏移最大的方向
+            if direction.abs() > state.direction.abs() {
+                state.direction = direction;
+            }
+            state.launch |= pressed(BUTTON_A) || pressed(BUTTON_B);
+            state.pause |= pressed(BUTTON_Y) || pressed(BUTTON_START);
+        }
+
+        state
+    }
+}
+
+// 去掉摇杆中心的死区，并把剩下的行程重新映射到0到1
+fn apply_dead_zone(value: f64, dead_zone: f64) -> f64 {
+    let magnitude = value.abs();
+    if magnitude <= dead_zone {
+        return 0.0;
+    }
+
+    value.signum() * ((magnitude - dead_zone) / (1.0 - dead_zone)).min(1.0)
+}
@@ -0,0 +1,232 @@
+// 排行榜：保存前10名的成绩
+//
+// 排行榜数据以JSON格式存放在实现了ScoreStorage的地方，浏览器中使用localStorage，
+// 没有localStorage可用时（例如禁用了存储的隐私模式）退回到只在内存中保存
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+use web_sys::{window, Storage};
+
+pub const MAX_ENTRIES: usize = 10;
+pub const MAX_INITIALS: usize = 3; // 名字缩写最多几个字符
+const STORAGE_KEY: &str = "wasm_game.high_scores";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub initials: String, // 名字缩写
+    pub score: u32,
+    pub level: u32,   // 到达的关卡
+    pub date: String, // 日期（UTC），格式为YYYY-MM-DD
+}
+
+// 排行榜数据的存放位置
+pub trait ScoreStorage {
+    fn load(&self) -> Option<String>;
+    fn save(&mut self, data: &str);
+}
+
+// 只保存在内存中，页面刷新后丢失
+#[derive(Default)]
+pub struct MemoryStorage {
+    data: Option<String>,
+}
+
+impl ScoreStorage for MemoryStorage {
+    fn load(&self) -> Option<String> {
+        self.data.clone()
+    }
+
+    fn save(&mut self, data: &str) {
+        self.data = Some(data.to_string());
+    }
+}
+
+// 保存在浏览器的localStorage中
+pub struct LocalStorage {
+    storage: Storage,
+}
+
+impl LocalStorage {
+    // 浏览器禁止访问localStorage时返回None
+    pub fn open() -> Option<Self> {
+        let storage = window()?.local_storage().ok()??;
+        Some(LocalStorage { storage })
+    }
+}
+
+impl ScoreStorage for LocalStorage {
+    fn load(&self) -> Option<String> {
+        self.storage.get_item(STORAGE_KEY).ok()?
+    }
+
+    fn save(&mut self, data: &str) {
+        // 存储空间已满时放弃保存，本次运行期间排行榜仍然有效
+        let _ = self.storage.set_item(STORAGE_KEY, data);
+    }
+}
+
+pub struct HighScoreTable {
+    entries: Vec<HighScore>, // 按分数从高到低排列
+    storage: Box<dyn ScoreStorage>,
+}
+
+impl HighScoreTable {
+    // 从存储中读取排行榜，数据不存在或已损坏时从空表开始
+    pub fn load(storage: Box<dyn ScoreStorage>) -> Self {
+        let entries = storage.load().and_then(|data| parse_entries(&data).ok()).unwrap_or_default();
+        HighScoreTable { entries, storage }
+    }
+
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+
+    // 这个分数能否进入排行榜
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0 && (self.entries.len() < MAX_ENTRIES || self.entries.iter().any(|entry| score > entry.score))
+    }
+
+    // 加入一条成绩并保存，返回它的名次（从0开始），没能进入排行榜时返回None
+    pub fn submit(&mut self, entry: HighScore) -> Option<usize> {
+        if !self.qualifies(entry.score) {
+            return None;
+        }
+
+        // 同分时先取得的成绩排在前面
+        let rank = self.entries.iter().position(|other| entry.score > other.score).unwrap_or(self.entries.len());
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_ENTRIES);
+        self.save();
+        Some(rank)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.entries).unwrap()
+    }
+
+    // 用JSON数据替换整个排行榜
+    pub fn import_json(&mut self, json: &str) -> Result<(), String> {
+        self.entries = parse_entries(json)?;
+        self.save();
+        Ok(())
+    }
+
+    fn save(&mut self) {
+        let json = self.to_json();
+        self.storage.save(&json);
+    }
+}
+
+// 解析并整理排行榜数据：检查名字缩写长度，按分数排序并只保留前10名
+fn parse_entries(json: &str) -> Result<Vec<HighScore>, String> {
+    let mut entries: Vec<HighScore> = serde_json::from_str(json).map_err(|error| format!("排行榜数据格式错误: {}", error))?;
+
+    if let Some(entry) = entries.iter().find(|entry| entry.initials.chars().count() > MAX_INITIALS) {
+        return Err(format!("名字缩写 \"{}\" 超过{}个字符", entry.initials, MAX_INITIALS));
+    }
+
+    entries.sort_by_key(|entry| Reverse(entry.score));
+    entries.truncate(MAX_ENTRIES);
+    Ok(entries)
+}
+
+// 把Unix时间（毫秒）转换成UTC日期字符串YYYY-MM-DD
+pub fn format_date(epoch_ms: f64) -> String {
+    // 从1970-01-01起的天数换算成公历日期，每400年为一个完整的周期
+    let days = (epoch_ms / 86_400_000.0).floor() as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153; // 从3月开始数的月份
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    // 与测试共享数据的存储，用来检查保存的内容
+    #[derive(Clone, Default)]
+    struct SharedStorage(Rc<RefCell<Option<String>>>);
+
+    impl ScoreStorage for SharedStorage {
+        fn load(&self) -> Option<String> {
+            self.0.borrow().clone()
+        }
+
+        fn save(&mut self, data: &str) {
+            *self.0.borrow_mut() = Some(data.to_string());
+        }
+    }
+
+    fn entry(initials: &str, score: u32) -> HighScore {
+        HighScore { initials: initials.to_string(), score, level: 1, date: "2024-05-01".to_string() }
+    }
+
+    #[test]
+    fn submit_keeps_the_table_sorted_and_limited() {
+        let mut table = HighScoreTable::load(Box::new(MemoryStorage::default()));
+        assert!(!table.qualifies(0));
+
+        for score in 1..=MAX_ENTRIES as u32 {
+            table.submit(entry("AAA", score * 10));
+        }
+        assert_eq!(table.entries().len(), MAX_ENTRIES);
+        assert!(!table.qualifies(10));
+        assert_eq!(table.submit(entry("LOW", 5)), None);
+
+        // 同分时先取得的成绩排在前面
+        assert_eq!(table.submit(entry("TIE", 50)), Some(6));
+        assert_eq!(table.entries()[5].initials, "AAA");
+        assert_eq!(table.entries().len(), MAX_ENTRIES);
+        assert_eq!(table.entries().last().unwrap().score, 20);
+        assert_eq!(table.submit(entry("TOP", 1000)), Some(0));
+    }
+
+    #[test]
+    fn table_is_saved_and_loaded_again() {
+        let storage = SharedStorage::default();
+        let mut table = HighScoreTable::load(Box::new(storage.clone()));
+        table.submit(entry("ABC", 120));
+        table.submit(entry("XYZ", 300));
+
+        let reloaded = HighScoreTable::load(Box::new(storage.clone()));
+        assert_eq!(reloaded.entries(), &[entry("XYZ", 300), entry("ABC", 120)]);
+
+        // 损坏的数据被忽略
+        *storage.0.borrow_mut() = Some("not json".to_string());
+        assert!(HighScoreTable::load(Box::new(storage)).entries().is_empty());
+    }
+
+    #[test]
+    fn import_sorts_entries_and_rejects_bad_data() {
+        let storage = SharedStorage::default();
+        let mut table = HighScoreTable::load(Box::new(storage.clone()));
+        let json = serde_json::to_string(&[entry("B", 20), entry("A", 30)]).unwrap();
+        table.import_json(&json).unwrap();
+        assert_eq!(table.entries(), &[entry("A", 30), entry("B", 20)]);
+        assert_eq!(storage.load(), Some(table.to_json()));
+
+        assert!(table.import_json("[1, 2]").is_err());
+        let long = serde_json::to_string(&[entry("ABCD", 10)]).unwrap();
+        assert!(table.import_json(&long).is_err());
+        // 导入失败时保留原来的排行榜
+        assert_eq!(table.entries().len(), 2);
+    }
+
+    #[test]
+    fn format_date_converts_epoch_milliseconds() {
+        assert_eq!(format_date(0.0), "1970-01-01");
+        assert_eq!(format_date(1_714_521_600_000.0), "2024-05-01");
+        assert_eq!(format_date(951_782_400_000.0 + 3_600_000.0), "2000-02-29");
+        assert_eq!(format_date(-1.0), "1969-12-31");
+    }
+}
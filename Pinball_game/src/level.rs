@@ -0,0 +1,290 @@
+// 关卡定义格式与加载
+//
+// 关卡使用逐行的文本格式，空行和以#开头的行会被忽略：
+//
+//     # 第一关
+//     name 入门
+//     brick 5 40 50 20
+//     brick 60 40 50 20 points=30 hp=3
+//     brick 115 40 50 20 type=steel
+//
+// name 指令设置关卡名称（可选，显示在顶部信息栏）；brick 指令依次给出砖块左上角的x、y坐标以及宽、高，
+// 后面可以跟若干 key=value 形式的可选属性：
+//
+//     points=<n>  击碎该砖块获得的分数，默认10
+//     hp=<n>      需要击中几次才会碎，默认1
+//     type=<t>    砖块种类：normal（普通，默认）、steel（无法击碎）、bomb（碎裂时炸毁周围砖块）
+use std::fmt;
+
+use crate::config::GameConfig;
+use crate::game::{Brick, BrickKind};
+
+const DEFAULT_POINTS: u32 = 10;
+
+// 解析后的关卡
+#[derive(Clone, Debug)]
+pub struct Level {
+    pub name: String,
+    pub bricks: Vec<BrickDef>,
+}
+
+// 关卡中的一块砖
+#[derive(Clone, Debug)]
+pub struct BrickDef {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub points: u32,
+    pub kind: BrickKind,
+    pub hits: u32,
+    pub line: usize, // 定义所在的行号，用于报告错误
+}
+
+// 关卡解析错误，带有出错的行号（从1开始，0表示与具体行无关）
+#[derive(Debug, PartialEq)]
+pub struct LevelError {
+    pub line: usize,
+    pub kind: LevelErrorKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LevelErrorKind {
+    UnknownDirective(String),                             // 无法识别的指令
+    MissingValue(&'static str),                           // 缺少必需的字段
+    InvalidNumber { field: &'static str, value: String }, // 字段不是合法的数字
+    UnknownAttribute(String),                             // 无法识别的可选属性
+    UnknownBrickType(String),                             // 无法识别的砖块种类
+    ZeroHitPoints,                                        // 耐久为0
+    NonPositiveSize,                                      // 宽或高不大于0
+    OutOfBounds { width: f64, height: f64 },              // 砖块超出可放置区域
+    Overlap { other_line: usize },                        // 与另一块砖重叠
+    NoBricks,                                             // 关卡中没有可以击碎的砖块
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "第{}行: ", self.line)?;
+        }
+
+        match &self.kind {
+            LevelErrorKind::UnknownDirective(directive) => write!(f, "无法识别的指令 \"{}\"", directive),
+            LevelErrorKind::MissingValue(field) => write!(f, "缺少字段 {}", field),
+            LevelErrorKind::InvalidNumber { field, value } => write!(f, "字段 {} 的值 \"{}\" 不是合法的数字", field, value),
+            LevelErrorKind::UnknownAttribute(attribute) => write!(f, "无法识别的属性 \"{}\"", attribute),
+            LevelErrorKind::UnknownBrickType(brick_type) => {
+                write!(f, "无法识别的砖块种类 \"{}\"（可选 normal、steel、bomb）", brick_type)
+            }
+            LevelErrorKind::ZeroHitPoints => write!(f, "砖块的耐久hp必须大于0"),
+            LevelErrorKind::NonPositiveSize => write!(f, "砖块的宽和高必须大于0"),
+            LevelErrorKind::OutOfBounds { width, height } => {
+                write!(f, "砖块超出可放置区域（x在0到{}之间，y在0到{}之间）", width, height)
+            }
+            LevelErrorKind::Overlap { other_line } => write!(f, "砖块与第{}行的砖块重叠", other_line),
+            LevelErrorKind::NoBricks => write!(f, "关卡中没有可以击碎的砖块"),
+        }
+    }
+}
+
+impl Level {
+    // 按场地配置解析关卡文本，遇到第一个错误时返回
+    pub fn parse(source: &str, config: &GameConfig) -> Result<Level, LevelError> {
+        let (area_width, area_height) = (config.canvas_width, config.brick_area_bottom());
+        let mut level = Level { name: String::new(), bricks: Vec::new() };
+
+        for (index, raw_line) in source.lines().enumerate() {
+            let line = index + 1;
+            let content = raw_line.trim();
+            if content.is_empty() || content.starts_with('#') {
+                continue;
+            }
+
+            let error = |kind| LevelError { line, kind };
+            let mut fields = content.split_whitespace();
+            match fields.next() {
+                Some("name") => {
+                    level.name = fields.collect::<Vec<_>>().join(" ");
+                }
+                Some("brick") => {
+                    let brick = parse_brick(line, &mut fields)?;
+
+                    if brick.width <= 0.0 || brick.height <= 0.0 {
+                        return Err(error(LevelErrorKind::NonPositiveSize));
+                    }
+                    if brick.x < 0.0
+                        || brick.y < 0.0
+                        || brick.x + brick.width > area_width
+                        || brick.y + brick.height > area_height
+                    {
+                        return Err(error(LevelErrorKind::OutOfBounds { width: area_width, height: area_height }));
+                    }
+                    if let Some(other) = level.bricks.iter().find(|other| overlaps(other, &brick)) {
+                        return Err(error(LevelErrorKind::Overlap { other_line: other.line }));
+                    }
+
+                    level.bricks.push(brick);
+                }
+                Some(directive) => return Err(error(LevelErrorKind::UnknownDirective(directive.to_string()))),
+                None => {}
+            }
+        }
+
+        // 只有无法击碎的砖块时这一关永远无法完成
+        if !level.bricks.iter().any(|brick| brick.kind != BrickKind::Unbreakable) {
+            return Err(LevelError { line: 0, kind: LevelErrorKind::NoBricks });
+        }
+
+        Ok(level)
+    }
+
+    // 根据关卡定义生成场上的砖块
+    pub fn create_bricks(&self) -> Vec<Brick> {
+        self.bricks
+            .iter()
+            .map(|def| Brick {
+                x: def.x,
+                y: def.y,
+                width: def.width,
+                height: def.height,
+                active: true,
+                points: def.points,
+                kind: def.kind,
+                hits_left: def.hits,
+                max_hits: def.hits,
+            })
+            .collect()
+    }
+}
+
+// 解析brick指令的参数
+fn parse_brick<'a>(line: usize, fields: &mut impl Iterator<Item = &'a str>) -> Result<BrickDef, LevelError> {
+    let mut number = |field: &'static str| -> Result<f64, LevelError> {
+        let value = fields.next().ok_or(LevelError { line, kind: LevelErrorKind::MissingValue(field) })?;
+        parse_number(line, field, value)
+    };
+
+    let x = number("x")?;
+    let y = number("y")?;
+    let width = number("width")?;
+    let height = number("height")?;
+    let mut brick = BrickDef { x, y, width, height, points: DEFAULT_POINTS, kind: BrickKind::Normal, hits: 1, line };
+
+    for attribute in fields {
+        match attribute.split_once('=') {
+            Some(("points", value)) => brick.points = parse_integer(line, "points", value)?,
+            Some(("hp", value)) => {
+                brick.hits = parse_integer(line, "hp", value)?;
+                if brick.hits == 0 {
+                    return Err(LevelError { line, kind: LevelErrorKind::ZeroHitPoints });
+                }
+            }
+            Some(("type", value)) => {
+                brick.kind = match value {
+                    "normal" => BrickKind::Normal,
+                    "steel" => BrickKind::Unbreakable,
+                    "bomb" => BrickKind::Explosive,
+                    _ => return Err(LevelError { line, kind: LevelErrorKind::UnknownBrickType(value.to_string()) }),
+                };
+            }
+            _ => {
+                return Err(LevelError { line, kind: LevelErrorKind::UnknownAttribute(attribute.to_string()) });
+            }
+        }
+    }
+
+    Ok(brick)
+}
+
+fn parse_number(line: usize, field: &'static str, value: &str) -> Result<f64, LevelError> {
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok(number),
+        _ => Err(LevelError { line, kind: LevelErrorKind::InvalidNumber { field, value: value.to_string() } }),
+    }
+}
+
+fn parse_integer(line: usize, field: &'static str, value: &str) -> Result<u32, LevelError> {
+    value
+        .parse()
+        .map_err(|_| LevelError { line, kind: LevelErrorKind::InvalidNumber { field, value: value.to_string() } })
+}
+
+fn overlaps(a: &BrickDef, b: &BrickDef) -> bool {
+    a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<Level, LevelError> {
+        Level::parse(source, &GameConfig::default())
+    }
+
+    fn error(source: &str) -> (usize, LevelErrorKind) {
+        let error = parse(source).unwrap_err();
+        (error.line, error.kind)
+    }
+
+    #[test]
+    fn parses_bricks_and_attributes() {
+        let level = parse("# 注释\n\nname 测试 关卡\nbrick 5 40 50 20\nbrick 60 40 50 20 points=30 hp=3 type=bomb\n").unwrap();
+        assert_eq!(level.name, "测试 关卡");
+        assert_eq!(level.bricks.len(), 2);
+
+        let brick = &level.bricks[1];
+        assert_eq!((brick.x, brick.y, brick.width, brick.height), (60.0, 40.0, 50.0, 20.0));
+        assert_eq!((brick.points, brick.hits, brick.kind, brick.line), (30, 3, BrickKind::Explosive, 5));
+        assert_eq!((level.bricks[0].points, level.bricks[0].hits), (DEFAULT_POINTS, 1));
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        assert_eq!(error("brick 5 40 50 20\nwall 1 2"), (2, LevelErrorKind::UnknownDirective("wall".to_string())));
+        assert_eq!(error("brick 5 40 50"), (1, LevelErrorKind::MissingValue("height")));
+        assert_eq!(
+            error("brick 5 abc 50 20"),
+            (1, LevelErrorKind::InvalidNumber { field: "y", value: "abc".to_string() })
+        );
+        assert_eq!(
+            error("brick 5 40 50 20 points=-1"),
+            (1, LevelErrorKind::InvalidNumber { field: "points", value: "-1".to_string() })
+        );
+        assert_eq!(error("brick 5 40 50 20 color=red"), (1, LevelErrorKind::UnknownAttribute("color=red".to_string())));
+        assert_eq!(error("\nbrick 5 40 50 20 hp=0"), (2, LevelErrorKind::ZeroHitPoints));
+        assert_eq!(error("brick 5 40 50 20 type=glass"), (1, LevelErrorKind::UnknownBrickType("glass".to_string())));
+        assert_eq!(error("brick 5 40 0 20"), (1, LevelErrorKind::NonPositiveSize));
+    }
+
+    #[test]
+    fn rejects_bricks_outside_the_area_or_overlapping() {
+        let config = GameConfig::default();
+        let bounds = || LevelErrorKind::OutOfBounds { width: config.canvas_width, height: config.brick_area_bottom() };
+        assert_eq!(error("brick -1 40 50 20"), (1, bounds()));
+        assert_eq!(error(&format!("brick 5 {} 50 20", config.brick_area_bottom() - 10.0)), (1, bounds()));
+
+        assert_eq!(
+            error("brick 5 40 50 20\nbrick 60 40 50 20\n# 与第2行重叠\nbrick 100 50 50 20"),
+            (4, LevelErrorKind::Overlap { other_line: 2 })
+        );
+        // 相邻但不重叠
+        assert!(parse("brick 5 40 50 20\nbrick 55 40 50 20").is_ok());
+    }
+
+    #[test]
+    fn needs_a_breakable_brick() {
+        assert_eq!(error(""), (0, LevelErrorKind::NoBricks));
+        assert_eq!(error("name 钢墙\nbrick 5 40 50 20 type=steel"), (0, LevelErrorKind::NoBricks));
+    }
+
+    #[test]
+    fn bundled_levels_are_valid() {
+        let classic = parse(include_str!("../levels/classic.txt")).unwrap();
+        assert_eq!(classic.name, "经典");
+        assert_eq!(classic.bricks.len(), 35);
+
+        let fortress = parse(include_str!("../levels/fortress.txt")).unwrap();
+        assert_eq!(fortress.name, "堡垒");
+        assert!(fortress.bricks.iter().any(|brick| brick.kind == BrickKind::Unbreakable));
+    }
+}
@@ -1,398 +1,687 @@
-use std::cell::RefCell;
-use std::f64;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{window, CanvasRenderingContext2d, HtmlCanvasElement, MouseEvent, Element};
-use rand::Rng;
-
-// 游戏常量
-const CANVAS_WIDTH: f64 = 440.0; // 新的宽度
-const CANVAS_HEIGHT: f64 = 330.0; // 新的高度
-const BALL_RADIUS: f64 = 8.0; // 缩小球的尺寸以适应新画布
-const PADDLE_WIDTH: f64 = 80.0; // 缩小挡板宽度
-const PADDLE_HEIGHT: f64 = 12.0; // 缩小挡板高度
-const PADDLE_Y: f64 = CANVAS_HEIGHT - 30.0;
-const BRICK_WIDTH: f64 = 50.0; // 调整砖块大小以适应新的宽度
-const BRICK_HEIGHT: f64 = 20.0;
-const BRICK_ROWS: usize = 5;
-const BRICK_COLS: usize = 7; // 调整列数以适应新的宽度
-const BRICK_TOP_OFFSET: f64 = 40.0;
-const BRICK_PADDING: f64 = 5.0;
-
-// 游戏状态
-struct GameState {
-    ball_x: f64,
-    ball_y: f64,
-    ball_dx: f64,
-    ball_dy: f64,
-    paddle_x: f64,
-    bricks: Vec<Brick>,
-    game_over: bool,
-    game_started: bool,
-    last_time: f64,  // 添加上一帧的时间戳
+use web_sys::{
+    window, AddEventListenerOptions, CanvasRenderingContext2d, Event, EventTarget, HtmlCanvasElement, HtmlElement,
+    KeyboardEvent, MouseEvent, PointerEvent, ResizeObserver,
+};
+
+mod audio;
+mod ball;
+mod collision;
+mod config;
+mod event;
+mod game;
+mod gamepad;
+mod highscore;
+mod level;
+mod particle;
+mod powerup;
+mod render;
+mod replay;
+mod speed;
+
+use audio::AudioPlayer;
+use config::GameConfig;
+use event::{GameEvent, GameEventKind};
+use game::{GameState, Input, DEFAULT_STARTING_LIVES};
+use gamepad::GamepadInput;
+use highscore::{HighScoreTable, LocalStorage};
+use level::Level;
+use replay::Replay;
+
+const TAP_SLOP: f64 = 10.0; // 手指按下到抬起之间移动不超过这个距离（逻辑像素）才算轻触
+
+// 注册在DOM上的事件监听，销毁游戏时需要移除
+struct Listener {
+    target: EventTarget,
+    event_type: &'static str,
+    closure: Closure<dyn FnMut(Event)>,
+}
+
+impl Listener {
+    fn new(target: &EventTarget, event_type: &'static str, handler: impl FnMut(Event) + 'static) -> Result<Self, JsValue> {
+        Self::with_options(target, event_type, &AddEventListenerOptions::new(), handler)
+    }
+
+    fn with_options(
+        target: &EventTarget,
+        event_type: &'static str,
+        options: &AddEventListenerOptions,
+        handler: impl FnMut(Event) + 'static,
+    ) -> Result<Self, JsValue> {
+        let closure = Closure::wrap(Box::new(handler) as Box<dyn FnMut(Event)>);
+        target.add_event_listener_with_callback_and_add_event_listener_options(
+            event_type,
+            closure.as_ref().unchecked_ref(),
+            options,
+        )?;
+        Ok(Listener { target: target.clone(), event_type, closure })
+    }
+
+    fn remove(&self) {
+        let _ = self
+            .target
+            .remove_event_listener_with_callback(self.event_type, self.closure.as_ref().unchecked_ref());
+    }
 }
 
-struct Brick {
-    x: f64,
-    y: f64,
-    width: f64,
-    height: f64,
-    active: bool,
+// 当前按住的方向键
+#[derive(Clone, Copy, Default)]
+struct HeldKeys {
+    left: bool,
+    right: bool,
 }
 
-// 初始化游戏状态
-impl GameState {
-    fn new() -> Self {
-        let mut rng = rand::thread_rng();
-        let bricks = create_bricks();
-        
-        // 初始球的速度和方向，但不立即使用
-        let angle = rng.gen_range((-60.0_f64).to_radians()..(60.0_f64).to_radians());
-        let speed = 300.0; // 调整基础速度为更大的值，因为我们会乘以delta time(秒)
-        
-        // 挡板初始位置
-        let paddle_x = (CANVAS_WIDTH - PADDLE_WIDTH) / 2.0;
-        
-        // 获取当前时间
-        let win = web_sys::window().unwrap();
-        let now = win.performance().unwrap().now();
-        
-        GameState {
-            // 球的初始位置在挡板上方中央
-            ball_x: paddle_x + PADDLE_WIDTH / 2.0,
-            ball_y: PADDLE_Y - BALL_RADIUS,
-            ball_dx: speed * angle.sin(), // 使用sin来计算水平速度
-            ball_dy: -speed * angle.cos(), // 使用cos来确保主要是垂直运动
-            paddle_x,
-            bricks,
-            game_over: false,
-            game_started: false,
-            last_time: now,
+impl HeldKeys {
+    // 换算成挡板的移动方向，同时按住左右时不动
+    fn direction(self) -> f64 {
+        match (self.left, self.right) {
+            (true, false) => -1.0,
+            (false, true) => 1.0,
+            _ => 0.0,
         }
     }
-    
-    // 重置游戏状态
-    fn reset(&mut self) {
-        let mut rng = rand::thread_rng();
-        let angle = rng.gen_range((-60.0_f64).to_radians()..(60.0_f64).to_radians());
-        let speed = 300.0; // 调整基础速度
-        
-        // 球位于挡板上方
-        self.ball_x = self.paddle_x + PADDLE_WIDTH / 2.0;
-        self.ball_y = PADDLE_Y - BALL_RADIUS;
-        self.ball_dx = speed * angle.sin(); // 使用sin来计算水平速度
-        self.ball_dy = -speed * angle.cos(); // 使用cos来确保主要是垂直运动
-        self.game_over = false;
-        self.game_started = false;
-        self.bricks = create_bricks();
-        
-        // 更新时间戳
-        let win = web_sys::window().unwrap();
-        self.last_time = win.performance().unwrap().now();
-    }
-    
-    // 更新游戏状态
-    fn update(&mut self) {
-        if self.game_over {
-            return;
+}
+
+// 游戏循环的回调，回调内部需要持有自身才能请求下一帧
+type FrameCallback = Rc<RefCell<Option<Closure<dyn FnMut()>>>>;
+
+// JS端通过on注册的事件回调
+type EventCallbacks = Rc<RefCell<Vec<(GameEventKind, js_sys::Function)>>>;
+
+// 可以嵌入到任意canvas上的弹球游戏
+#[wasm_bindgen]
+pub struct PinballGame {
+    game_state: Rc<RefCell<GameState>>,
+    canvas: HtmlCanvasElement,
+    context: CanvasRenderingContext2d,
+    listeners: Vec<Listener>,
+    resize_observer: ResizeObserver,
+    _resize_callback: Closure<dyn FnMut()>, // 需要和resize_observer活得一样久
+    gamepad: Rc<RefCell<GamepadInput>>,
+    frame: FrameCallback,
+    animation_id: Rc<Cell<Option<i32>>>, // 已请求但尚未执行的动画帧
+    callbacks: EventCallbacks,
+    audio: Rc<RefCell<AudioPlayer>>,
+}
+
+#[wasm_bindgen]
+impl PinballGame {
+    // 创建游戏，canvas可以是canvas元素的id，也可以是canvas元素本身；不传config时使用默认的440×330场地
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas: JsValue, config: Option<GameConfig>) -> Result<PinballGame, JsValue> {
+        // 设置panic hook
+        console_error_panic_hook::set_once();
+
+        // 获取Canvas元素和上下文
+        let canvas: HtmlCanvasElement = match canvas.as_string() {
+            Some(canvas_id) => window()
+                .unwrap()
+                .document()
+                .unwrap()
+                .get_element_by_id(&canvas_id)
+                .ok_or_else(|| JsValue::from_str("找不到指定ID的canvas元素"))?
+                .dyn_into::<HtmlCanvasElement>()
+                .map_err(|_| JsValue::from_str("所提供的元素不是canvas"))?,
+            None => canvas.dyn_into::<HtmlCanvasElement>().map_err(|_| JsValue::from_str("所提供的元素不是canvas"))?,
+        };
+
+        let config = config.unwrap_or_default();
+        config.validate().map_err(|error| JsValue::from_str(&error))?;
+
+        // 绘图缓冲区先与场地一致，canvas在页面上显示出来后再按显示尺寸和像素比调整
+        canvas.set_width(config.canvas_width as u32);
+        canvas.set_height(config.canvas_height as u32);
+        fit_canvas(&canvas);
+
+        // canvas的显示尺寸变化时（例如容器随窗口缩放）重新调整绘图缓冲区
+        let resize_callback = {
+            let canvas = canvas.clone();
+            Closure::wrap(Box::new(move || fit_canvas(&canvas)) as Box<dyn FnMut()>)
+        };
+        let resize_observer = ResizeObserver::new(resize_callback.as_ref().unchecked_ref())?;
+        resize_observer.observe(&canvas);
+
+        let context = canvas
+            .get_context("2d")?
+            .unwrap()
+            .dyn_into::<CanvasRenderingContext2d>()?;
+
+        // 创建游戏状态，没有指定种子时随机选一个
+        let seed = rand::random();
+        let mut state = GameState::new(config, DEFAULT_STARTING_LIVES, seed, now());
+        state.time_origin = window().unwrap().performance().unwrap().time_origin();
+
+        // 排行榜保存在localStorage中，浏览器不允许访问时只保存在内存中
+        if let Some(storage) = LocalStorage::open() {
+            state.high_scores = HighScoreTable::load(Box::new(storage));
         }
+        let game_state = Rc::new(RefCell::new(state));
+        let mut listeners = Vec::new();
 
-        // 如果游戏尚未开始，球跟随挡板移动
-        if !self.game_started {
-            self.ball_x = self.paddle_x + PADDLE_WIDTH / 2.0;
-            self.ball_y = PADDLE_Y - BALL_RADIUS;
-            
-            // 即使在游戏未开始时也更新上一帧时间
-            let win = web_sys::window().unwrap();
-            self.last_time = win.performance().unwrap().now();
-            return;
+        // 触摸时不滚动或缩放页面，手指的移动全部交给游戏处理
+        canvas.style().set_property("touch-action", "none")?;
+
+        // 处理指针移动事件（鼠标、触控笔和手指），挡板跟随指针
+        {
+            let game_state = game_state.clone();
+            let canvas_element = canvas.clone();
+            listeners.push(Listener::new(&canvas, "pointermove", move |event| {
+                let event: PointerEvent = event.unchecked_into();
+                if !event.is_primary() {
+                    return;
+                }
+
+                // 更新挡板位置，确保不超出边界
+                let (pointer_x, _) = to_game_coords(&canvas_element, &config, &event);
+                game_state.borrow_mut().move_paddle_to(pointer_x);
+            })?);
         }
 
-        // 计算时间差（delta time）
-        let win = web_sys::window().unwrap();
-        let now = win.performance().unwrap().now();
-        let delta_time = (now - self.last_time) / 1000.0; // 转换为秒
-        self.last_time = now;
-        
-        // 限制delta_time，防止在切换标签页等情况下delta_time过大导致球穿模
-        let delta_time = f64::min(delta_time, 0.1);
-
-        // 更新球的位置，使用delta_time使运动与帧率无关
-        self.ball_x += self.ball_dx * delta_time;
-        self.ball_y += self.ball_dy * delta_time;
-        
-        // 检测边界碰撞
-        if self.ball_x - BALL_RADIUS <= 0.0 || self.ball_x + BALL_RADIUS >= CANVAS_WIDTH {
-            self.ball_dx = -self.ball_dx;
+        // 处理手指按下和抬起：按下时挡板立即移到手指下方，没有拖动的轻触相当于点击
+        let tap_start = Rc::new(Cell::new(None::<f64>));
+        {
+            let game_state = game_state.clone();
+            let canvas_element = canvas.clone();
+            let tap_start = tap_start.clone();
+            listeners.push(Listener::new(&canvas, "pointerdown", move |event| {
+                let event: PointerEvent = event.unchecked_into();
+                if event.pointer_type() != "touch" || !event.is_primary() {
+                    return;
+                }
+
+                // 手指移出canvas后仍然能继续控制挡板
+                let _ = canvas_element.set_pointer_capture(event.pointer_id());
+
+                let (touch_x, _) = to_game_coords(&canvas_element, &config, &event);
+                tap_start.set(Some(touch_x));
+                game_state.borrow_mut().move_paddle_to(touch_x);
+            })?);
         }
-        
-        if self.ball_y - BALL_RADIUS <= 0.0 {
-            self.ball_dy = -self.ball_dy;
+        {
+            let game_state = game_state.clone();
+            let canvas_element = canvas.clone();
+            listeners.push(Listener::new(&canvas, "pointerup", move |event| {
+                let event: PointerEvent = event.unchecked_into();
+                if event.pointer_type() != "touch" || !event.is_primary() {
+                    return;
+                }
+
+                let (touch_x, _) = to_game_coords(&canvas_element, &config, &event);
+                if let Some(start_x) = tap_start.take() {
+                    if (touch_x - start_x).abs() < TAP_SLOP {
+                        game_state.borrow_mut().handle_input(Input::Click, now());
+                    }
+                }
+            })?);
         }
-        
-        // 检测游戏结束
-        if self.ball_y + BALL_RADIUS >= CANVAS_HEIGHT {
-            self.game_over = true;
+
+        // 阻止触摸的默认行为，这样页面不会滚动，浏览器也不会在轻触后再模拟出一次click
+        {
+            let options = AddEventListenerOptions::new();
+            options.set_passive(false);
+            listeners.push(Listener::with_options(&canvas, "touchstart", &options, |event| {
+                event.prevent_default();
+            })?);
         }
-        
-        // 检测挡板碰撞
-        if self.ball_y + BALL_RADIUS >= PADDLE_Y && 
-           self.ball_y + BALL_RADIUS <= PADDLE_Y + PADDLE_HEIGHT &&
-           self.ball_x >= self.paddle_x && 
-           self.ball_x <= self.paddle_x + PADDLE_WIDTH 
+
+        // 处理点击事件（开始游戏、发球、继续或进入下一关）
         {
-            // 根据击中挡板的位置计算反弹角度
-            let relative_intersect_x = (self.paddle_x + PADDLE_WIDTH / 2.0) - self.ball_x;
-            let normalized_intersect_x = relative_intersect_x / (PADDLE_WIDTH / 2.0);
-            let bounce_angle = normalized_intersect_x * 60.0_f64.to_radians();
-            
-            // 保持相同的速度大小，只改变方向
-            let current_speed = (self.ball_dx.powi(2) + self.ball_dy.powi(2)).sqrt();
-            // 根据当前速度重新计算dx和dy分量
-            self.ball_dx = current_speed * bounce_angle.sin();
-            self.ball_dy = -current_speed * bounce_angle.cos().abs(); // 向上反弹
+            let game_state = game_state.clone();
+            let canvas_element = canvas.clone();
+            listeners.push(Listener::new(&canvas, "click", move |event| {
+                let event: MouseEvent = event.unchecked_into();
+                let (click_x, click_y) = to_game_coords(&canvas_element, &config, &event);
+
+                // 确保点击在canvas内部
+                if (0.0..=config.canvas_width).contains(&click_x) && (0.0..=config.canvas_height).contains(&click_y) {
+                    game_state.borrow_mut().handle_input(Input::Click, now());
+                }
+            })?);
         }
-        
-        // 检测砖块碰撞
-        for brick in &mut self.bricks {
-            if !brick.active {
-                continue;
-            }
-            
-            // 使用更精确的碰撞检测
-            // 计算球心与砖块边缘的最近点
-            let closest_x = f64::max(brick.x, f64::min(self.ball_x, brick.x + brick.width));
-            let closest_y = f64::max(brick.y, f64::min(self.ball_y, brick.y + brick.height));
-            
-            // 计算球心与最近点之间的距离
-            let distance_x = self.ball_x - closest_x;
-            let distance_y = self.ball_y - closest_y;
-            let distance_squared = distance_x * distance_x + distance_y * distance_y;
-            
-            // 如果距离小于球半径，则发生碰撞
-            if distance_squared < BALL_RADIUS * BALL_RADIUS {
-                brick.active = false;
-                
-                // 确定应该反弹的方向
-                // 从哪个方向碰撞更多，就从那个方向反弹
-                if distance_x.abs() > distance_y.abs() {
-                    self.ball_dx = -self.ball_dx;
-                } else {
-                    self.ball_dy = -self.ball_dy;
+
+        // 处理键盘事件：方向键或A、D移动挡板，空格发球，P或Esc暂停；
+        // 输入排行榜的名字缩写时，字母和数字键用于输入，退格删除，回车或空格确认
+        // canvas默认无法获得焦点，所以监听整个窗口，但不处理页面上输入框里的按键
+        let window = window().unwrap();
+        let held_keys = Rc::new(Cell::new(HeldKeys::default()));
+        {
+            let game_state = game_state.clone();
+            let held_keys = held_keys.clone();
+            listeners.push(Listener::new(&window, "keydown", move |event| {
+                let event: KeyboardEvent = event.unchecked_into();
+                // 页面上的输入框等正在接收键盘输入，不抢它的按键
+                if is_editable_target(&event) {
+                    return;
                 }
-                
-                break; // 每次只处理一个碰撞
-            }
+                if game_state.borrow().is_entering_initials() {
+                    let mut game_state = game_state.borrow_mut();
+                    match event.key().as_str() {
+                        "Backspace" => game_state.erase_initial(),
+                        "Enter" | " " if !event.repeat() => game_state.handle_input(Input::Click, now()),
+                        key => match key.chars().collect::<Vec<_>>().as_slice() {
+                            [c] => game_state.type_initial(*c),
+                            _ => return,
+                        },
+                    }
+                    event.prevent_default();
+                    return;
+                }
+
+                let mut keys = held_keys.get();
+                match event.code().as_str() {
+                    "ArrowLeft" | "KeyA" => keys.left = true,
+                    "ArrowRight" | "KeyD" => keys.right = true,
+                    // 按住不放时的自动重复不算新的输入
+                    "Space" if !event.repeat() => game_state.borrow_mut().handle_input(Input::Click, now()),
+                    "KeyP" | "Escape" if !event.repeat() => {
+                        game_state.borrow_mut().handle_input(Input::TogglePause, now())
+                    }
+                    "Space" | "KeyP" | "Escape" => {}
+                    _ => return,
+                }
+
+                // 阻止方向键和空格滚动页面
+                event.prevent_default();
+                held_keys.set(keys);
+                game_state.borrow_mut().set_paddle_direction(keys.direction());
+            })?);
         }
-        
-        // 检查是否清空所有砖块
-        if !self.bricks.iter().any(|brick| brick.active) {
-            self.reset();
+        {
+            let game_state = game_state.clone();
+            let held_keys = held_keys.clone();
+            listeners.push(Listener::new(&window, "keyup", move |event| {
+                let event: KeyboardEvent = event.unchecked_into();
+                let mut keys = held_keys.get();
+                match event.code().as_str() {
+                    "ArrowLeft" | "KeyA" => keys.left = false,
+                    "ArrowRight" | "KeyD" => keys.right = false,
+                    _ => return,
+                }
+
+                held_keys.set(keys);
+                game_state.borrow_mut().set_paddle_direction(keys.direction());
+            })?);
         }
+        {
+            // 窗口失去焦点时自动暂停，回来后需要玩家主动继续；
+            // 失去焦点后也收不到keyup，把按键状态清空，避免挡板一直移动
+            let game_state = game_state.clone();
+            listeners.push(Listener::new(&window, "blur", move |_| {
+                held_keys.set(HeldKeys::default());
+                let mut game_state = game_state.borrow_mut();
+                game_state.set_paddle_direction(0.0);
+                game_state.handle_input(Input::Pause, now());
+            })?);
+        }
+
+        // 切换标签页或最小化窗口时自动暂停
+        {
+            let game_state = game_state.clone();
+            let document = window.document().unwrap();
+            listeners.push(Listener::new(&document.clone(), "visibilitychange", move |_| {
+                if document.hidden() {
+                    game_state.borrow_mut().handle_input(Input::Pause, now());
+                }
+            })?);
+        }
+
+        Ok(PinballGame {
+            game_state,
+            canvas,
+            context,
+            listeners,
+            resize_observer,
+            _resize_callback: resize_callback,
+            gamepad: Rc::new(RefCell::new(GamepadInput::default())),
+            frame: Rc::new(RefCell::new(None)),
+            animation_id: Rc::new(Cell::new(None)),
+            callbacks: Rc::new(RefCell::new(Vec::new())),
+            audio: Rc::new(RefCell::new(AudioPlayer::default())),
+        })
     }
-    
-    // 渲染游戏
-    #[allow(deprecated)]
-    fn render(&self, ctx: &CanvasRenderingContext2d) {
-        // 清空画布
-        ctx.clear_rect(0.0, 0.0, CANVAS_WIDTH, CANVAS_HEIGHT);
-        
-        // 绘制球
-        ctx.begin_path();
-        ctx.arc(self.ball_x, self.ball_y, BALL_RADIUS, 0.0, f64::consts::PI * 2.0).unwrap();
-        ctx.set_fill_style(&JsValue::from_str("black"));
-        ctx.fill();
-        ctx.close_path();
-        
-        // 绘制挡板
-        ctx.begin_path();
-        ctx.rect(self.paddle_x, PADDLE_Y, PADDLE_WIDTH, PADDLE_HEIGHT);
-        ctx.set_fill_style(&JsValue::from_str("black"));
-        ctx.fill();
-        ctx.close_path();
-        
-        // 绘制砖块
-        for brick in &self.bricks {
-            if brick.active {
-                ctx.begin_path();
-                ctx.rect(brick.x, brick.y, brick.width, brick.height);
-                ctx.set_fill_style(&JsValue::from_str("black"));
-                ctx.fill();
-                ctx.set_stroke_style(&JsValue::from_str("white"));
-                ctx.stroke();
-                ctx.close_path();
+
+    // 开始游戏循环，已经在运行时不做任何事
+    pub fn start(&mut self) {
+        if self.animation_id.get().is_some() {
+            return;
+        }
+
+        let game_state = self.game_state.clone();
+        let canvas = self.canvas.clone();
+        let context = self.context.clone();
+        let gamepad = self.gamepad.clone();
+        let mut pixel_ratio = window().unwrap().device_pixel_ratio();
+        let frame = self.frame.clone();
+        let animation_id = self.animation_id.clone();
+        let callbacks = self.callbacks.clone();
+        let audio = self.audio.clone();
+        *self.frame.borrow_mut() = Some(Closure::wrap(Box::new(move || {
+            let now = now();
+
+            // 轮询手柄输入
+            gamepad.borrow_mut().poll(&mut game_state.borrow_mut(), now);
+
+            // 更新游戏状态
+            game_state.borrow_mut().update(now);
+
+            // 把这一帧产生的事件转发给JS，回调中可能再调用游戏的方法，所以先释放借用
+            let events = std::mem::take(&mut game_state.borrow_mut().events);
+            dispatch_events(&callbacks, &events);
+
+            // 播放这一帧产生的音效
+            let sounds = std::mem::take(&mut game_state.borrow_mut().sounds);
+            audio.borrow_mut().play(&sounds);
+
+            // 窗口移到像素比不同的显示器上或者页面缩放时，显示尺寸不一定变化，需要单独检查
+            let current_ratio = window().unwrap().device_pixel_ratio();
+            if current_ratio != pixel_ratio {
+                pixel_ratio = current_ratio;
+                fit_canvas(&canvas);
+            }
+
+            // 渲染游戏：按绘图缓冲区与场地的比例等比缩放并居中，游戏内部始终使用逻辑坐标；
+            // 容器不是4:3时两侧或上下留出空白
+            {
+                let game_state = game_state.borrow();
+                let (width, height) = (canvas.width() as f64, canvas.height() as f64);
+                let (scale, offset_x, offset_y) = letterbox(width, height, &game_state.config);
+                context.set_transform(1.0, 0.0, 0.0, 1.0, 0.0, 0.0).unwrap();
+                context.clear_rect(0.0, 0.0, width, height);
+                context.set_transform(scale, 0.0, 0.0, scale, offset_x, offset_y).unwrap();
+                game_state.render(&context);
+            }
+
+            // 请求下一帧
+            if let Some(callback) = frame.borrow().as_ref() {
+                animation_id.set(Some(request_animation_frame(callback)));
             }
+        }) as Box<dyn FnMut()>));
+
+        self.animation_id.set(Some(request_animation_frame(self.frame.borrow().as_ref().unwrap())));
+    }
+
+    // 暂停游戏，画面停留在暂停界面
+    pub fn pause(&mut self) {
+        self.game_state.borrow_mut().handle_input(Input::Pause, now());
+    }
+
+    // 从暂停中继续游戏
+    pub fn resume(&mut self) {
+        self.game_state.borrow_mut().handle_input(Input::Resume, now());
+    }
+
+    // 暂停或继续游戏
+    #[wasm_bindgen(js_name = togglePause)]
+    pub fn toggle_pause(&mut self) {
+        self.game_state.borrow_mut().handle_input(Input::TogglePause, now());
+    }
+
+    // 停止游戏循环并移除所有事件监听，之后这个实例不再使用canvas
+    pub fn destroy(&mut self) {
+        if let Some(id) = self.animation_id.take() {
+            let _ = window().unwrap().cancel_animation_frame(id);
         }
-        
-        // 显示游戏提示
-        if self.game_over {
-            // 设置文本对齐方式为居中
-            ctx.set_text_align("center");
-            ctx.set_text_baseline("middle");
-            
-            // 游戏结束文字 - 居中并向下移动
-            ctx.set_font("36px Arial");
-            ctx.set_fill_style(&JsValue::from_str("black"));
-            ctx.fill_text("游戏结束", CANVAS_WIDTH / 2.0, CANVAS_HEIGHT / 2.0 + 20.0).unwrap();
-            
-            // 重新开始提示文字 - 居中并向下移动
-            ctx.set_font("20px Arial");
-            ctx.fill_text("点击屏幕重新开始", CANVAS_WIDTH / 2.0, CANVAS_HEIGHT / 2.0 + 70.0).unwrap();
-        } else if !self.game_started {
-            // 游戏未开始时显示提示 - 居中并向下移动
-            ctx.set_text_align("center");
-            ctx.set_text_baseline("middle");
-            ctx.set_font("20px Arial");
-            ctx.set_fill_style(&JsValue::from_str("black"));
-            ctx.fill_text("点击屏幕开始游戏", CANVAS_WIDTH / 2.0, CANVAS_HEIGHT / 2.0 + 50.0).unwrap();
+        // 游戏循环的回调持有自身的引用，这里手动释放
+        self.frame.borrow_mut().take();
+
+        for listener in self.listeners.drain(..) {
+            listener.remove();
         }
+        self.resize_observer.disconnect();
     }
-}
 
-// 创建砖块
-fn create_bricks() -> Vec<Brick> {
-    let mut bricks = Vec::new();
-    let mut rng = rand::thread_rng();
-    
-    for row in 0..BRICK_ROWS {
-        for col in 0..BRICK_COLS {
-            // 随机确定是否创建砖块
-            if rng.gen_bool(0.8) { // 80%概率创建砖块
-                let brick = Brick {
-                    x: col as f64 * (BRICK_WIDTH + BRICK_PADDING) + BRICK_PADDING,
-                    y: row as f64 * (BRICK_HEIGHT + BRICK_PADDING) + BRICK_TOP_OFFSET,
-                    width: BRICK_WIDTH,
-                    height: BRICK_HEIGHT,
-                    active: true,
-                };
-                bricks.push(brick);
-            }
+    // 注册事件回调，event可以是gameStarted、brickDestroyed、lifeLost、levelCleared或gameOver；
+    // 回调收到一个对象，包含type、score、level、lives和remainingBricks
+    pub fn on(&mut self, event: &str, callback: js_sys::Function) -> Result<(), JsValue> {
+        let kind = GameEventKind::from_name(event).ok_or_else(|| JsValue::from_str(&format!("未知的事件 \"{}\"", event)))?;
+        self.callbacks.borrow_mut().push((kind, callback));
+        Ok(())
+    }
+
+    // 移除用on注册的事件回调
+    pub fn off(&mut self, event: &str, callback: &js_sys::Function) {
+        if let Some(kind) = GameEventKind::from_name(event) {
+            self.callbacks.borrow_mut().retain(|(other_kind, other)| !(*other_kind == kind && other == callback));
         }
     }
-    
-    bricks
+
+    // 音效音量，0到1之间
+    #[wasm_bindgen(getter)]
+    pub fn volume(&self) -> f64 {
+        self.audio.borrow().volume()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_volume(&mut self, volume: f64) {
+        self.audio.borrow_mut().set_volume(volume);
+    }
+
+    // 是否静音
+    #[wasm_bindgen(getter)]
+    pub fn muted(&self) -> bool {
+        self.audio.borrow().is_muted()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_muted(&mut self, muted: bool) {
+        self.audio.borrow_mut().set_muted(muted);
+    }
+
+    // 设置手柄摇杆的死区（0到1之间），摇杆偏移不超过该值时挡板不动
+    #[wasm_bindgen(js_name = setGamepadDeadZone)]
+    pub fn set_gamepad_dead_zone(&mut self, dead_zone: f64) {
+        self.gamepad.borrow_mut().set_dead_zone(dead_zone);
+    }
+
+    // 当前使用的随机数种子，记下它就可以重现同样的砖块布局
+    #[wasm_bindgen(getter)]
+    pub fn seed(&self) -> u32 {
+        self.game_state.borrow().seed
+    }
+
+    // 设置随机数种子，从下一局开始生效
+    #[wasm_bindgen(js_name = setSeed)]
+    pub fn set_seed(&mut self, seed: u32) {
+        self.game_state.borrow_mut().set_seed(seed);
+    }
+
+    // 开始每日挑战：种子由当天的UTC日期决定，同一天所有玩家得到相同的砖块布局
+    #[wasm_bindgen(js_name = startDailyChallenge)]
+    pub fn start_daily_challenge(&mut self) {
+        let date = js_sys::Date::new_0();
+        let seed = daily_seed(date.get_utc_full_year(), date.get_utc_month() + 1, date.get_utc_date());
+        self.game_state.borrow_mut().start_with_seed(seed, now());
+    }
+
+    // 导出最近一局的录像，还没有开始过游戏或者回放过录像之后返回undefined
+    #[wasm_bindgen(js_name = exportReplay)]
+    pub fn export_replay(&self) -> Option<String> {
+        self.game_state.borrow().recorder.as_ref().map(|recorder| recorder.replay.encode())
+    }
+
+    // 回放exportReplay导出的录像；需要使用与录制时相同的场地配置和关卡
+    #[wasm_bindgen(js_name = playReplay)]
+    pub fn play_replay(&mut self, data: &str) -> Result<(), JsValue> {
+        let replay = Replay::decode(data).map_err(|error| JsValue::from_str(&error.to_string()))?;
+        self.game_state.borrow_mut().start_playback(replay, now());
+        Ok(())
+    }
+
+    // 停止回放，玩家从当前状态接着玩
+    #[wasm_bindgen(js_name = stopReplay)]
+    pub fn stop_replay(&mut self) {
+        self.game_state.borrow_mut().stop_playback();
+    }
+
+    // 是否正在回放录像
+    #[wasm_bindgen(getter, js_name = isReplaying)]
+    pub fn is_replaying(&self) -> bool {
+        self.game_state.borrow().playback.is_some()
+    }
+
+    // 导出排行榜，格式为JSON数组，每一项包含initials、score、level和date
+    #[wasm_bindgen(js_name = exportHighScores)]
+    pub fn export_high_scores(&self) -> String {
+        self.game_state.borrow().high_scores.to_json()
+    }
+
+    // 导入exportHighScores导出的排行榜，替换现有的记录
+    #[wasm_bindgen(js_name = importHighScores)]
+    pub fn import_high_scores(&mut self, json: &str) -> Result<(), JsValue> {
+        self.game_state.borrow_mut().high_scores.import_json(json).map_err(|error| JsValue::from_str(&error))
+    }
+
+    // 设置每局开始时的生命数，对尚未开始的一局立即生效
+    #[wasm_bindgen(js_name = setStartingLives)]
+    pub fn set_starting_lives(&mut self, lives: u32) {
+        self.game_state.borrow_mut().set_starting_lives(lives);
+    }
+
+    // 从文本加载关卡布局，格式错误时返回带行号的错误描述
+    #[wasm_bindgen(js_name = loadLevel)]
+    pub fn load_level(&mut self, source: &str) -> Result<(), JsValue> {
+        let level = Level::parse(source, &self.game_state.borrow().config).map_err(|error| JsValue::from_str(&error.to_string()))?;
+        self.game_state.borrow_mut().load_level(level, now());
+        Ok(())
+    }
 }
 
-// 初始化游戏
-#[wasm_bindgen(start)]
-pub fn start() -> Result<(), JsValue> {
-    // 设置panic hook
-    console_error_panic_hook::set_once();
-    
-    // 获取Canvas元素和上下文
-    let window = window().unwrap();
-    let document = window.document().unwrap();
-    let canvas = document.get_element_by_id("canvas").unwrap();
-    let canvas: HtmlCanvasElement = canvas.dyn_into::<HtmlCanvasElement>().unwrap();
-    
-    let context = canvas
-        .get_context("2d")?
-        .unwrap()
-        .dyn_into::<CanvasRenderingContext2d>()
-        .unwrap();
-    
-    // 创建游戏状态
-    let game_state = Rc::new(RefCell::new(GameState::new()));
-    
-    // 处理鼠标移动事件
-    {
-        let game_state = game_state.clone();
-        let canvas_element = canvas.clone().dyn_into::<Element>().unwrap();
-        let closure = Closure::wrap(Box::new(move |event: MouseEvent| {
-            let rect = canvas_element.get_bounding_client_rect();
-            
-            // 计算缩放比例 - canvas实际渲染尺寸与游戏内部逻辑尺寸的比例
-            let scale_x = rect.width() / CANVAS_WIDTH;
-            
-            // 计算鼠标在canvas中的相对位置，并应用缩放比例
-            let mouse_x = (event.client_x() as f64 - rect.left()) / scale_x;
-            
-            // 更新挡板位置，确保不超出边界
-            let mut state = game_state.borrow_mut();
-            state.paddle_x = mouse_x - PADDLE_WIDTH / 2.0;
-            
-            if state.paddle_x < 0.0 {
-                state.paddle_x = 0.0;
-            } else if state.paddle_x + PADDLE_WIDTH > CANVAS_WIDTH {
-                state.paddle_x = CANVAS_WIDTH - PADDLE_WIDTH;
-            }
-        }) as Box<dyn FnMut(_)>);
-        
-        canvas.add_event_listener_with_callback("mousemove", closure.as_ref().unchecked_ref())?;
-        closure.forget();
+impl Drop for PinballGame {
+    // JS端调用free()时同样需要清理
+    fn drop(&mut self) {
+        self.destroy();
     }
-    
-    // 处理点击事件（开始游戏或重新开始游戏）
-    {
-        let game_state = game_state.clone();
-        let canvas_element = canvas.clone().dyn_into::<Element>().unwrap();
-        let closure = Closure::wrap(Box::new(move |event: MouseEvent| {
-            let rect = canvas_element.get_bounding_client_rect();
-            
-            // 计算缩放比例
-            let scale_x = rect.width() / CANVAS_WIDTH;
-            let scale_y = rect.height() / CANVAS_HEIGHT;
-            
-            // 计算点击在canvas中的相对位置
-            let click_x = (event.client_x() as f64 - rect.left()) / scale_x;
-            let click_y = (event.client_y() as f64 - rect.top()) / scale_y;
-            
-            // 确保点击在canvas内部
-            if click_x >= 0.0 && click_x <= CANVAS_WIDTH && click_y >= 0.0 && click_y <= CANVAS_HEIGHT {
-                let mut state = game_state.borrow_mut();
-                if state.game_over {
-                    *state = GameState::new();
-                } else if !state.game_started {
-                    // 如果游戏尚未开始，点击时给球一个随机方向
-                    let mut rng = rand::thread_rng();
-                    let angle = rng.gen_range((-60.0_f64).to_radians()..(60.0_f64).to_radians());
-                    let speed = 300.0; // 调整为更大的基础速度，因为我们会乘以delta time
-                    
-                    state.ball_dx = speed * angle.sin(); // 使用sin来计算水平速度
-                    state.ball_dy = -speed * angle.cos(); // 使用cos来确保主要是垂直运动
-                    state.game_started = true;
-                    
-                    // 重置时间戳，确保第一帧的delta time合理
-                    let win = web_sys::window().unwrap();
-                    state.last_time = win.performance().unwrap().now();
-                }
-            }
-        }) as Box<dyn FnMut(_)>);
-        
-        canvas.add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())?;
-        closure.forget();
+}
+
+// 辅助函数：把鼠标或手指在页面上的位置换算成游戏内部的逻辑坐标
+fn to_game_coords(canvas: &HtmlCanvasElement, config: &GameConfig, event: &MouseEvent) -> (f64, f64) {
+    let rect = canvas.get_bounding_client_rect();
+
+    // 边框不属于绘图区域，按内容区域的位置和大小换算
+    let left = rect.left() + canvas.client_left() as f64;
+    let top = rect.top() + canvas.client_top() as f64;
+
+    // 与渲染使用相同的等比缩放和居中偏移，canvas实际显示尺寸与游戏内部逻辑尺寸的比例
+    let (scale, offset_x, offset_y) = letterbox(canvas.client_width() as f64, canvas.client_height() as f64, config);
+
+    // 计算在canvas中的相对位置，去掉留白并应用缩放比例
+    let x = (event.client_x() as f64 - left - offset_x) / scale;
+    let y = (event.client_y() as f64 - top - offset_y) / scale;
+    (x, y)
+}
+
+// 辅助函数：把场地等比缩放后居中放进width×height的区域，返回缩放比例和左上角的偏移
+fn letterbox(width: f64, height: f64, config: &GameConfig) -> (f64, f64, f64) {
+    let scale = (width / config.canvas_width).min(height / config.canvas_height);
+    let offset_x = (width - config.canvas_width * scale) / 2.0;
+    let offset_y = (height - config.canvas_height * scale) / 2.0;
+    (scale, offset_x, offset_y)
+}
+
+// 辅助函数：事件是否发生在可以输入文字的元素上（输入框、文本框、下拉框或可编辑的元素）
+fn is_editable_target(event: &Event) -> bool {
+    let Some(element) = event.target().and_then(|target| target.dyn_into::<HtmlElement>().ok()) else {
+        return false;
+    };
+    element.is_content_editable() || matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+}
+
+// 辅助函数：按canvas的显示尺寸和devicePixelRatio设置绘图缓冲区的大小，让高分屏上的画面保持清晰
+fn fit_canvas(canvas: &HtmlCanvasElement) {
+    // 还没有显示在页面上时保持原来的大小
+    if canvas.client_width() == 0 || canvas.client_height() == 0 {
+        return;
     }
-    
-    // 设置游戏循环
-    {
-        let game_state = game_state.clone();
-        let f = Rc::new(RefCell::new(None));
-        let g = f.clone();
-        
-        *g.borrow_mut() = Some(Closure::wrap(Box::new(move || {
-            // 更新游戏状态
-            game_state.borrow_mut().update();
-            
-            // 渲染游戏
-            game_state.borrow().render(&context);
-            
-            // 请求下一帧
-            request_animation_frame(f.borrow().as_ref().unwrap());
-        }) as Box<dyn FnMut()>));
-        
-        request_animation_frame(g.borrow().as_ref().unwrap());
+
+    // 页面没有用CSS设置canvas的显示尺寸时，显示尺寸由绘图缓冲区的大小决定，
+    // 放大缓冲区会让显示尺寸跟着变大，再触发ResizeObserver无限增大。
+    // 试着改变缓冲区大小，显示尺寸跟着变化就说明没有CSS尺寸，这时把当前的显示尺寸写进canvas的style
+    let (client_width, client_height) = (canvas.client_width() as u32, canvas.client_height() as u32);
+    pin_display_size(canvas, client_width, client_height);
+
+    let ratio = window().unwrap().device_pixel_ratio();
+    let width = (client_width as f64 * ratio).round() as u32;
+    let height = (client_height as f64 * ratio).round() as u32;
+
+    // 重新设置尺寸会清空画布，只在变化时设置
+    if canvas.width() != width || canvas.height() != height {
+        canvas.set_width(width);
+        canvas.set_height(height);
     }
-    
-    Ok(())
 }
 
-// 辅助函数：请求动画帧
-fn request_animation_frame(f: &Closure<dyn FnMut()>) {
+// 辅助函数：检查canvas的宽和高是否跟随绘图缓冲区变化，是则把当前计算出的显示尺寸固定到style中
+fn pin_display_size(canvas: &HtmlCanvasElement, client_width: u32, client_height: u32) {
+    let style = canvas.style();
+    let unset = |property: &str| style.get_property_value(property).unwrap_or_default().is_empty();
+    if !unset("width") && !unset("height") {
+        return;
+    }
+    let Ok(Some(computed)) = window().unwrap().get_computed_style(canvas) else {
+        return;
+    };
+    let computed_width = computed.get_property_value("width").unwrap_or_default();
+    let computed_height = computed.get_property_value("height").unwrap_or_default();
+
+    // 读取clientWidth会立即重新布局，所以能看到缓冲区变化后的显示尺寸
+    let (width, height) = (canvas.width(), canvas.height());
+    canvas.set_width(width + 1);
+    canvas.set_height(height + 1);
+    let follows_width = canvas.client_width() as u32 != client_width;
+    let follows_height = canvas.client_height() as u32 != client_height;
+    canvas.set_width(width);
+    canvas.set_height(height);
+
+    if follows_width && unset("width") {
+        let _ = style.set_property("width", &computed_width);
+    }
+    if follows_height && unset("height") {
+        let _ = style.set_property("height", &computed_height);
+    }
+}
+
+// 辅助函数：依次调用每个事件对应的回调，回调抛出的异常输出到控制台，不影响游戏继续运行
+fn dispatch_events(callbacks: &EventCallbacks, events: &[GameEvent]) {
+    for event in events {
+        // 回调中可能注册或移除回调，先复制出这次要调用的回调
+        let listeners: Vec<js_sys::Function> = callbacks
+            .borrow()
+            .iter()
+            .filter(|(kind, _)| *kind == event.kind)
+            .map(|(_, callback)| callback.clone())
+            .collect();
+        if listeners.is_empty() {
+            continue;
+        }
+
+        let payload = event.to_js();
+        for callback in listeners {
+            if let Err(error) = callback.call1(&JsValue::NULL, &payload) {
+                web_sys::console::error_1(&error);
+            }
+        }
+    }
+}
+
+// 辅助函数：由日期得到每日挑战的种子，例如2024年5月1日为20240501
+fn daily_seed(year: u32, month: u32, day: u32) -> u32 {
+    year * 10000 + month * 100 + day
+}
+
+// 辅助函数：读取浏览器的高精度时间戳（毫秒），作为游戏状态的时间源
+fn now() -> f64 {
+    window().unwrap().performance().unwrap().now()
+}
+
+// 辅助函数：请求动画帧，返回可以用于取消的ID
+fn request_animation_frame(f: &Closure<dyn FnMut()>) -> i32 {
     web_sys::window()
         .unwrap()
         .request_animation_frame(f.as_ref().unchecked_ref())
-        .expect("should register `requestAnimationFrame` OK");
+        .expect("should register `requestAnimationFrame` OK")
 }
 
 // 设置panic hook以便于调试
@@ -0,0 +1,90 @@
+// 碎片粒子：砖块碎裂时从砖块所在位置向外飞出，受重力下落并逐渐淡出
+//
+// 字段沿用Particles项目粒子中的x、y、size、speed_x、speed_y、color和opacity，去掉了只用于3D效果的z；
+// 另外加上寿命life和max_life，透明度随剩余寿命降低，寿命耗尽的粒子被移除
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+
+const MAX_PARTICLES: usize = 240; // 同时存在的粒子上限，超出后不再生成新的碎片
+const PARTICLES_PER_BRICK: usize = 12;
+const GRAVITY: f64 = 500.0; // 重力加速度（像素/秒²）
+const MIN_SPEED: f64 = 60.0; // 飞出速度范围（像素/秒）
+const MAX_SPEED: f64 = 180.0;
+const MIN_LIFE: f64 = 0.4; // 寿命范围（秒）
+const MAX_LIFE: f64 = 0.8;
+
+pub struct Particle {
+    pub x: f64,
+    pub y: f64,
+    pub size: f64,
+    speed_x: f64,
+    speed_y: f64,
+    pub color: String,
+    pub opacity: f64,
+    life: f64,     // 剩余寿命（秒）
+    max_life: f64, // 初始寿命（秒）
+}
+
+impl Particle {
+    fn update(&mut self, delta_time: f64) {
+        self.speed_y += GRAVITY * delta_time;
+        self.x += self.speed_x * delta_time;
+        self.y += self.speed_y * delta_time;
+
+        // 随剩余寿命淡出
+        self.life -= delta_time;
+        self.opacity = (self.life / self.max_life).max(0.0);
+    }
+
+    fn is_alive(&self) -> bool {
+        self.life > 0.0
+    }
+}
+
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    rng: Pcg32, // 碎片只影响画面，使用单独的随机数生成器，不改变游戏本身的随机序列
+}
+
+impl ParticleSystem {
+    pub fn new(seed: u32) -> Self {
+        ParticleSystem { particles: Vec::new(), rng: Pcg32::seed_from_u64(seed as u64) }
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    // 在砖块所在的矩形内生成一批碎片，从砖块中心向外飞出并略微向上抛起
+    pub fn burst(&mut self, x: f64, y: f64, width: f64, height: f64, color: &str) {
+        let count = PARTICLES_PER_BRICK.min(MAX_PARTICLES - self.particles.len());
+        let (center_x, center_y) = (x + width / 2.0, y + height / 2.0);
+
+        for _ in 0..count {
+            let particle_x = self.rng.gen_range(x..x + width);
+            let particle_y = self.rng.gen_range(y..y + height);
+            let angle = (particle_y - center_y).atan2(particle_x - center_x) + self.rng.gen_range(-0.4..0.4);
+            let speed = self.rng.gen_range(MIN_SPEED..MAX_SPEED);
+            let life = self.rng.gen_range(MIN_LIFE..MAX_LIFE);
+
+            self.particles.push(Particle {
+                x: particle_x,
+                y: particle_y,
+                size: self.rng.gen_range(2.0..4.5),
+                speed_x: angle.cos() * speed,
+                speed_y: angle.sin() * speed - MIN_SPEED,
+                color: color.to_string(),
+                opacity: 1.0,
+                life,
+                max_life: life,
+            });
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f64) {
+        for particle in &mut self.particles {
+            particle.update(delta_time);
+        }
+        self.particles.retain(Particle::is_alive);
+    }
+}
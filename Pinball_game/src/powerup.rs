@@ -0,0 +1,129 @@
+// 道具：砖块碎裂时可能掉落胶囊，用挡板接住后获得效果
+use rand::Rng;
+
+use crate::collision::Rect;
+
+pub const CAPSULE_WIDTH: f64 = 30.0;
+pub const CAPSULE_HEIGHT: f64 = 12.0;
+const CAPSULE_FALL_SPEED: f64 = 100.0; // 胶囊下落速度（像素/秒）
+pub const DROP_CHANCE: f64 = 0.15; // 砖块碎裂时掉落胶囊的概率
+pub const EFFECT_DURATION: f64 = 10.0; // 限时效果的持续时间（秒）
+
+pub const LASER_COOLDOWN: f64 = 0.3; // 两次发射激光之间的最短间隔（秒）
+const LASER_SPEED: f64 = 500.0; // 激光上升速度（像素/秒）
+pub const LASER_LENGTH: f64 = 10.0;
+
+// 道具种类
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PowerUpKind {
+    Wide,      // 挡板变宽
+    Narrow,    // 挡板变窄
+    Slow,      // 球减速
+    Sticky,    // 球碰到挡板后粘住，点击再发射
+    ExtraLife, // 增加一条生命
+    Laser,     // 挡板可以发射激光击打砖块
+    MultiBall, // 每个球分裂成三个
+}
+
+impl PowerUpKind {
+    const ALL: [PowerUpKind; 7] = [
+        PowerUpKind::Wide,
+        PowerUpKind::Narrow,
+        PowerUpKind::Slow,
+        PowerUpKind::Sticky,
+        PowerUpKind::ExtraLife,
+        PowerUpKind::Laser,
+        PowerUpKind::MultiBall,
+    ];
+
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self::ALL[rng.gen_range(0..Self::ALL.len())]
+    }
+
+    // 胶囊上显示的字母
+    pub fn label(self) -> &'static str {
+        match self {
+            PowerUpKind::Wide => "E",
+            PowerUpKind::Narrow => "R",
+            PowerUpKind::Slow => "S",
+            PowerUpKind::Sticky => "C",
+            PowerUpKind::ExtraLife => "P",
+            PowerUpKind::Laser => "L",
+            PowerUpKind::MultiBall => "D",
+        }
+    }
+
+    // 胶囊的颜色
+    pub fn color(self) -> &'static str {
+        match self {
+            PowerUpKind::Wide => "#2980b9",
+            PowerUpKind::Narrow => "#7f8c8d",
+            PowerUpKind::Slow => "#e67e22",
+            PowerUpKind::Sticky => "#27ae60",
+            PowerUpKind::ExtraLife => "#8e44ad",
+            PowerUpKind::Laser => "#c0392b",
+            PowerUpKind::MultiBall => "#16a085",
+        }
+    }
+}
+
+// 正在下落的道具胶囊，(x, y)为胶囊中心
+pub struct Capsule {
+    pub x: f64,
+    pub y: f64,
+    pub kind: PowerUpKind,
+}
+
+impl Capsule {
+    pub fn update(&mut self, delta_time: f64) {
+        self.y += CAPSULE_FALL_SPEED * delta_time;
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect {
+            x: self.x - CAPSULE_WIDTH / 2.0,
+            y: self.y - CAPSULE_HEIGHT / 2.0,
+            width: CAPSULE_WIDTH,
+            height: CAPSULE_HEIGHT,
+        }
+    }
+}
+
+// 挡板射出的激光，(x, y)为激光的顶端
+pub struct LaserBolt {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl LaserBolt {
+    pub fn update(&mut self, delta_time: f64) {
+        self.y -= LASER_SPEED * delta_time;
+    }
+}
+
+// 正在生效的限时效果
+pub struct ActiveEffect {
+    pub kind: PowerUpKind,
+    pub remaining: f64, // 剩余时间（秒）
+}
+
+// 获得一个限时效果；同种效果重新计时，变宽和变窄互相抵消
+pub fn add_effect(effects: &mut Vec<ActiveEffect>, kind: PowerUpKind) {
+    effects.retain(|effect| {
+        let replaced = effect.kind == kind;
+        let cancelled = matches!(
+            (effect.kind, kind),
+            (PowerUpKind::Wide, PowerUpKind::Narrow) | (PowerUpKind::Narrow, PowerUpKind::Wide)
+        );
+        !replaced && !cancelled
+    });
+    effects.push(ActiveEffect { kind, remaining: EFFECT_DURATION });
+}
+
+// 推进所有效果的计时，移除已经结束的效果
+pub fn tick_effects(effects: &mut Vec<ActiveEffect>, delta_time: f64) {
+    for effect in effects.iter_mut() {
+        effect.remaining -= delta_time;
+    }
+    effects.retain(|effect| effect.remaining > 0.0);
+}
@@ -0,0 +1,358 @@
+use std::f64;
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use crate::ball::MAX_SPIN;
+use crate::game::{Brick, BrickKind, GameState, Phase};
+use crate::highscore::MAX_INITIALS;
+use crate::particle::Particle;
+use crate::powerup::{Capsule, PowerUpKind, LASER_LENGTH};
+
+const HUD_Y: f64 = 20.0; // 顶部信息栏文字的垂直位置
+const GAME_OVER_ROWS: f64 = 17.5; // 游戏结束画面的高度相当于排行榜的多少行
+
+impl GameState {
+    // 渲染游戏
+    pub fn render(&self, ctx: &CanvasRenderingContext2d) {
+        // 清空画布
+        ctx.clear_rect(0.0, 0.0, self.config.canvas_width, self.config.canvas_height);
+
+        // 每个阶段负责自己的画面
+        match self.phase {
+            Phase::Title => self.render_title(ctx),
+            Phase::Serving => self.render_serving(ctx),
+            Phase::Playing => self.render_playfield(ctx),
+            Phase::Paused => self.render_paused(ctx),
+            Phase::LevelCleared => self.render_level_cleared(ctx),
+            Phase::GameOver => self.render_game_over(ctx),
+        }
+    }
+
+    // 标题界面
+    #[allow(deprecated)]
+    fn render_title(&self, ctx: &CanvasRenderingContext2d) {
+        ctx.set_text_align("center");
+        ctx.set_text_baseline("middle");
+        ctx.set_fill_style(&JsValue::from_str("black"));
+
+        ctx.set_font("36px Arial");
+        ctx.fill_text("弹球游戏", self.config.canvas_width / 2.0, self.config.canvas_height / 2.0 - 20.0).unwrap();
+
+        ctx.set_font("20px Arial");
+        ctx.fill_text("点击屏幕开始游戏", self.config.canvas_width / 2.0, self.config.canvas_height / 2.0 + 40.0).unwrap();
+    }
+
+    // 等待发球
+    #[allow(deprecated)]
+    fn render_serving(&self, ctx: &CanvasRenderingContext2d) {
+        self.render_playfield(ctx);
+
+        // 提示文字 - 居中并向下移动
+        ctx.set_text_align("center");
+        ctx.set_text_baseline("middle");
+        ctx.set_font("20px Arial");
+        ctx.set_fill_style(&JsValue::from_str("black"));
+        ctx.fill_text("点击屏幕发球", self.config.canvas_width / 2.0, self.config.canvas_height / 2.0 + 50.0).unwrap();
+    }
+
+    // 暂停
+    fn render_paused(&self, ctx: &CanvasRenderingContext2d) {
+        self.render_playfield(ctx);
+        self.render_overlay(ctx, "暂停", "点击屏幕或按P键继续");
+    }
+
+    // 本关完成
+    fn render_level_cleared(&self, ctx: &CanvasRenderingContext2d) {
+        self.render_playfield(ctx);
+        self.render_overlay(ctx, &format!("第 {} 关完成", self.level), "点击屏幕进入下一关");
+    }
+
+    // 游戏结束
+    #[allow(deprecated)]
+    fn render_game_over(&self, ctx: &CanvasRenderingContext2d) {
+        self.render_playfield(ctx);
+        ctx.set_fill_style(&JsValue::from_str("rgba(255, 255, 255, 0.85)"));
+        ctx.fill_rect(0.0, 0.0, self.config.canvas_width, self.config.canvas_height);
+
+        // 标题、得分、排行榜和提示从上到下排列，整体在场地中垂直居中；
+        // 间距和字号都以排行榜的行高为单位，随场地高度缩放
+        let row_height = self.config.canvas_height / GAME_OVER_ROWS;
+        let font = |bold: &str, rows: f64| format!("{}{:.1}px Arial", bold, row_height * rows);
+        let center_x = self.config.canvas_width / 2.0;
+        let top = self.config.canvas_height / 2.0 - row_height * 7.5;
+        ctx.set_text_align("center");
+        ctx.set_text_baseline("middle");
+        ctx.set_fill_style(&JsValue::from_str("black"));
+
+        ctx.set_font(&font("", 1.5));
+        ctx.fill_text("游戏结束", center_x, top).unwrap();
+
+        // 成绩进入排行榜时在得分下方输入名字缩写，光标用下划线表示
+        ctx.set_font(&font("", 0.95));
+        let score_y = top + row_height * 1.5;
+        let hint = match &self.initials_entry {
+            Some(initials) => {
+                ctx.set_fill_style(&JsValue::from_str("#e67e22"));
+                let cursor = if initials.len() < MAX_INITIALS { "_" } else { "" };
+                ctx.fill_text(&format!("新纪录 {}！名字缩写: {}{}", self.score, initials, cursor), center_x, score_y).unwrap();
+                "输入字母或数字，按回车或点击屏幕确认"
+            }
+            None => {
+                ctx.fill_text(&format!("最终得分: {}", self.score), center_x, score_y).unwrap();
+                "点击屏幕返回标题"
+            }
+        };
+
+        // 表头加10行成绩之后空出一行再显示提示
+        self.render_high_scores(ctx, top + row_height * 2.9, row_height);
+
+        ctx.set_font(&font("", 0.85));
+        ctx.set_fill_style(&JsValue::from_str("black"));
+        ctx.fill_text(hint, center_x, top + row_height * 15.0).unwrap();
+    }
+
+    // 绘制排行榜，y为表头的位置
+    #[allow(deprecated)]
+    fn render_high_scores(&self, ctx: &CanvasRenderingContext2d, y: f64, row_height: f64) {
+        let font = |bold: &str| format!("{}{:.1}px Arial", bold, row_height * 0.75);
+
+        // 各列的中心位置按场地宽度的比例排列
+        let columns = [0.12, 0.28, 0.46, 0.62, 0.82].map(|ratio| self.config.canvas_width * ratio);
+        let draw_row = |cells: [String; 5], row_y: f64| {
+            for (cell, x) in cells.iter().zip(columns) {
+                ctx.fill_text(cell, x, row_y).unwrap();
+            }
+        };
+
+        ctx.set_font(&font("bold "));
+        ctx.set_fill_style(&JsValue::from_str("#7f8c8d"));
+        draw_row(["名次", "名字", "分数", "关卡", "日期"].map(String::from), y);
+
+        let entries = self.high_scores.entries();
+        ctx.set_font(&font(""));
+        if entries.is_empty() {
+            ctx.fill_text("暂无记录", self.config.canvas_width / 2.0, y + row_height).unwrap();
+            return;
+        }
+
+        for (rank, entry) in entries.iter().enumerate() {
+            // 本局刚加入的成绩高亮显示
+            let color = if self.new_high_score == Some(rank) { "#e67e22" } else { "black" };
+            ctx.set_fill_style(&JsValue::from_str(color));
+            draw_row(
+                [
+                    (rank + 1).to_string(),
+                    entry.initials.clone(),
+                    entry.score.to_string(),
+                    entry.level.to_string(),
+                    entry.date.clone(),
+                ],
+                y + row_height * (rank + 1) as f64,
+            );
+        }
+    }
+
+    // 绘制场上的球、挡板、砖块和顶部信息栏
+    #[allow(deprecated)]
+    fn render_playfield(&self, ctx: &CanvasRenderingContext2d) {
+        // 绘制球（在最近两个物理步之间插值，使不同刷新率下运动同样平滑）
+        let alpha = self.render_alpha();
+        for ball in &self.balls {
+            let (ball_x, ball_y) = ball.interpolated(alpha);
+            ctx.set_fill_style(&JsValue::from_str("black"));
+            ctx.begin_path();
+            ctx.arc(ball_x, ball_y, self.config.ball_radius, 0.0, f64::consts::PI * 2.0).unwrap();
+            ctx.fill();
+            ctx.close_path();
+
+            // 旋转的球上画一个随之转动的小白点，转得越快越明显
+            if ball.spin != 0.0 {
+                let marker_x = ball_x + ball.angle.cos() * self.config.ball_radius * 0.55;
+                let marker_y = ball_y + ball.angle.sin() * self.config.ball_radius * 0.55;
+                ctx.set_global_alpha((ball.spin.abs() / MAX_SPIN).min(1.0) * 0.8);
+                ctx.set_fill_style(&JsValue::from_str("white"));
+                ctx.begin_path();
+                ctx.arc(marker_x, marker_y, self.config.ball_radius * 0.25, 0.0, f64::consts::PI * 2.0).unwrap();
+                ctx.fill();
+                ctx.close_path();
+                ctx.set_global_alpha(1.0);
+            }
+        }
+
+        // 绘制挡板
+        self.render_paddle(ctx);
+
+        // 绘制砖块
+        for brick in &self.bricks {
+            if brick.active {
+                render_brick(ctx, brick);
+            }
+        }
+
+        // 绘制砖块碎片
+        for particle in self.debris.particles() {
+            render_particle(ctx, particle);
+        }
+        ctx.set_global_alpha(1.0);
+
+        // 绘制道具胶囊和激光
+        for capsule in &self.capsules {
+            render_capsule(ctx, capsule);
+        }
+        ctx.set_stroke_style(&JsValue::from_str("#e74c3c"));
+        ctx.set_line_width(2.0);
+        ctx.begin_path();
+        for laser in &self.lasers {
+            ctx.move_to(laser.x, laser.y);
+            ctx.line_to(laser.x, laser.y + LASER_LENGTH);
+        }
+        ctx.stroke();
+        ctx.set_line_width(1.0);
+
+        // 绘制分数、关卡和生命
+        self.render_hud(ctx);
+    }
+
+    // 绘制挡板，粘性和激光道具生效时改变外观
+    #[allow(deprecated)]
+    fn render_paddle(&self, ctx: &CanvasRenderingContext2d) {
+        let paddle_width = self.paddle_width();
+        let color = if self.has_effect(PowerUpKind::Sticky) { PowerUpKind::Sticky.color() } else { "black" };
+
+        ctx.begin_path();
+        ctx.rect(self.paddle_x, self.config.paddle_y(), paddle_width, self.config.paddle_height);
+        ctx.set_fill_style(&JsValue::from_str(color));
+        ctx.fill();
+        ctx.close_path();
+
+        // 激光炮管画在挡板两端
+        if self.has_effect(PowerUpKind::Laser) {
+            ctx.set_fill_style(&JsValue::from_str(PowerUpKind::Laser.color()));
+            ctx.fill_rect(self.paddle_x + 2.0, self.config.paddle_y() - 4.0, 4.0, 4.0);
+            ctx.fill_rect(self.paddle_x + paddle_width - 6.0, self.config.paddle_y() - 4.0, 4.0, 4.0);
+        }
+    }
+
+    // 在场景上覆盖一层半透明白色，并显示标题和提示文字
+    #[allow(deprecated)]
+    fn render_overlay(&self, ctx: &CanvasRenderingContext2d, title: &str, hint: &str) {
+        ctx.set_fill_style(&JsValue::from_str("rgba(255, 255, 255, 0.75)"));
+        ctx.fill_rect(0.0, 0.0, self.config.canvas_width, self.config.canvas_height);
+
+        // 设置文本对齐方式为居中
+        ctx.set_text_align("center");
+        ctx.set_text_baseline("middle");
+        ctx.set_fill_style(&JsValue::from_str("black"));
+
+        ctx.set_font("36px Arial");
+        ctx.fill_text(title, self.config.canvas_width / 2.0, self.config.canvas_height / 2.0 - 20.0).unwrap();
+
+        ctx.set_font("20px Arial");
+        ctx.fill_text(hint, self.config.canvas_width / 2.0, self.config.canvas_height / 2.0 + 60.0).unwrap();
+    }
+
+    // 绘制顶部的分数、关卡和生命信息
+    #[allow(deprecated)]
+    fn render_hud(&self, ctx: &CanvasRenderingContext2d) {
+        ctx.set_font("14px Arial");
+        ctx.set_text_baseline("middle");
+        ctx.set_fill_style(&JsValue::from_str("black"));
+
+        ctx.set_text_align("left");
+        ctx.fill_text(&format!("分数: {}", self.score), 10.0, HUD_Y).unwrap();
+
+        ctx.set_text_align("center");
+        // 加载的关卡有名称时显示在关卡数后面
+        let level_text = match self.custom_level.as_ref().filter(|level| !level.name.is_empty()) {
+            Some(level) => format!("关卡: {} {}", self.level, level.name),
+            None => format!("关卡: {}", self.level),
+        };
+        ctx.fill_text(&level_text, self.config.canvas_width / 2.0, HUD_Y).unwrap();
+
+        ctx.set_text_align("right");
+        ctx.fill_text(&format!("生命: {}", self.lives), self.config.canvas_width - 10.0, HUD_Y).unwrap();
+
+        // 回放录像时在信息栏下方提示
+        if self.playback.is_some() {
+            ctx.set_text_align("center");
+            ctx.set_fill_style(&JsValue::from_str("#c0392b"));
+            ctx.fill_text("回放中", self.config.canvas_width / 2.0, HUD_Y + 16.0).unwrap();
+        }
+    }
+}
+
+// 根据砖块种类和损坏程度绘制砖块
+#[allow(deprecated)]
+fn render_brick(ctx: &CanvasRenderingContext2d, brick: &Brick) {
+    ctx.begin_path();
+    ctx.rect(brick.x, brick.y, brick.width, brick.height);
+    ctx.set_fill_style(&JsValue::from_str(&brick.color()));
+    ctx.fill();
+    ctx.set_stroke_style(&JsValue::from_str("white"));
+    ctx.stroke();
+    ctx.close_path();
+
+    ctx.set_stroke_style(&JsValue::from_str("white"));
+    ctx.set_line_width(1.0);
+    match brick.kind {
+        // 无法击碎的砖块画上斜线纹理
+        BrickKind::Unbreakable => {
+            ctx.begin_path();
+            let mut offset = 0.0;
+            while offset < brick.width {
+                let end = f64::min(offset + brick.height, brick.width);
+                ctx.move_to(brick.x + offset, brick.y + brick.height);
+                ctx.line_to(brick.x + end, brick.y + brick.height - (end - offset));
+                offset += 10.0;
+            }
+            ctx.stroke();
+        }
+        // 爆炸砖块中间画一个圆形标记
+        BrickKind::Explosive => {
+            let (center_x, center_y) = brick.center();
+            ctx.begin_path();
+            ctx.arc(center_x, center_y, brick.height / 4.0, 0.0, f64::consts::PI * 2.0).unwrap();
+            ctx.stroke();
+        }
+        // 受损的砖块画出裂纹，损坏越多裂纹越多
+        BrickKind::Normal => {
+            let cracks = brick.max_hits - brick.hits_left;
+            ctx.begin_path();
+            for crack in 0..cracks {
+                let start_x = brick.x + brick.width * (crack + 1) as f64 / (cracks + 1) as f64;
+                ctx.move_to(start_x, brick.y);
+                ctx.line_to(start_x - brick.width * 0.1, brick.y + brick.height * 0.5);
+                ctx.line_to(start_x + brick.width * 0.05, brick.y + brick.height);
+            }
+            ctx.stroke();
+        }
+    }
+}
+
+// 绘制一块碎片：正方形的小块，透明度随寿命降低
+#[allow(deprecated)]
+fn render_particle(ctx: &CanvasRenderingContext2d, particle: &Particle) {
+    ctx.set_global_alpha(particle.opacity);
+    ctx.set_fill_style(&JsValue::from_str(&particle.color));
+    ctx.fill_rect(particle.x - particle.size / 2.0, particle.y - particle.size / 2.0, particle.size, particle.size);
+}
+
+// 绘制道具胶囊：带颜色的圆角胶囊，中间写着道具字母
+#[allow(deprecated)]
+fn render_capsule(ctx: &CanvasRenderingContext2d, capsule: &Capsule) {
+    let rect = capsule.rect();
+    let radius = rect.height / 2.0;
+
+    ctx.begin_path();
+    ctx.arc(rect.x + radius, capsule.y, radius, f64::consts::PI / 2.0, f64::consts::PI * 1.5).unwrap();
+    ctx.arc(rect.x + rect.width - radius, capsule.y, radius, f64::consts::PI * 1.5, f64::consts::PI / 2.0).unwrap();
+    ctx.close_path();
+    ctx.set_fill_style(&JsValue::from_str(capsule.kind.color()));
+    ctx.fill();
+
+    ctx.set_font("bold 10px Arial");
+    ctx.set_text_align("center");
+    ctx.set_text_baseline("middle");
+    ctx.set_fill_style(&JsValue::from_str("white"));
+    ctx.fill_text(capsule.kind.label(), capsule.x, capsule.y).unwrap();
+}
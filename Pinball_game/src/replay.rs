@@ -0,0 +1,257 @@
+// 录像：记录一局中影响模拟的所有输入，回放时按同样的顺序重新输入即可完全重现这一局
+//
+// 录像从一局开始时记录，内容是随机数种子、初始生命数和一串按顺序排列的记录。
+// 物理步之间发生的输入（挡板位置、键盘方向、点击）记在对应的物理步之前，
+// 连续的物理步合并成一条记录。文本格式用空格分隔，例如：
+//
+//     PB1 20240501 3 p180 d0 t95 c t240 p200.5 t3 ...
+//
+// 其中PB1是格式版本，后面依次是种子和初始生命数；tN表示N个物理步，pX表示挡板移到X，
+// dX表示键盘方向变为X，c表示一次点击。浮点数按能精确还原的最短形式写出
+use std::fmt;
+
+const FORMAT_VERSION: &str = "PB1";
+
+// 一条录像记录
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Record {
+    Ticks(u32),     // 连续推进若干个物理步
+    Paddle(f64),    // 挡板左端移到该位置
+    Direction(f64), // 键盘控制的挡板方向变为该值
+    Click,          // 玩家点击（发球、释放粘住的球、发射激光或进入下一关）
+}
+
+#[derive(Clone, Debug)]
+pub struct Replay {
+    pub seed: u32,
+    pub starting_lives: u32,
+    pub records: Vec<Record>,
+}
+
+// 录像解析错误
+#[derive(Debug, PartialEq)]
+pub enum ReplayError {
+    UnsupportedVersion(String), // 不是这个版本的录像
+    MissingHeader,              // 缺少种子或初始生命数
+    InvalidToken(String),       // 无法识别的记录
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::UnsupportedVersion(version) => write!(f, "不支持的录像格式 \"{}\"", version),
+            ReplayError::MissingHeader => write!(f, "录像缺少种子或初始生命数"),
+            ReplayError::InvalidToken(token) => write!(f, "无法识别的录像记录 \"{}\"", token),
+        }
+    }
+}
+
+impl Replay {
+    pub fn new(seed: u32, starting_lives: u32) -> Self {
+        Replay { seed, starting_lives, records: Vec::new() }
+    }
+
+    // 转换成文本格式
+    pub fn encode(&self) -> String {
+        let mut text = format!("{} {} {}", FORMAT_VERSION, self.seed, self.starting_lives);
+        for record in &self.records {
+            let token = match record {
+                Record::Ticks(count) => format!(" t{}", count),
+                Record::Paddle(x) => format!(" p{}", x),
+                Record::Direction(direction) => format!(" d{}", direction),
+                Record::Click => " c".to_string(),
+            };
+            text.push_str(&token);
+        }
+        text
+    }
+
+    // 从文本格式解析
+    pub fn decode(text: &str) -> Result<Replay, ReplayError> {
+        let mut tokens = text.split_whitespace();
+
+        let version = tokens.next().unwrap_or_default();
+        if version != FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version.to_string()));
+        }
+        let seed = tokens.next().and_then(|token| token.parse().ok()).ok_or(ReplayError::MissingHeader)?;
+        let starting_lives = tokens.next().and_then(|token| token.parse().ok()).ok_or(ReplayError::MissingHeader)?;
+
+        let mut replay = Replay::new(seed, starting_lives);
+        for token in tokens {
+            let invalid = || ReplayError::InvalidToken(token.to_string());
+            // 记录类型是第一个字符，按字符而不是字节切分，避免非ASCII字符导致panic
+            let (kind, value) = token.split_at(token.chars().next().map_or(0, char::len_utf8));
+            let record = match kind {
+                "t" => Record::Ticks(value.parse().map_err(|_| invalid())?),
+                "p" => Record::Paddle(parse_finite(value).ok_or_else(invalid)?),
+                "d" => Record::Direction(parse_finite(value).ok_or_else(invalid)?),
+                "c" if value.is_empty() => Record::Click,
+                _ => return Err(invalid()),
+            };
+            replay.records.push(record);
+        }
+
+        Ok(replay)
+    }
+}
+
+fn parse_finite(value: &str) -> Option<f64> {
+    value.parse::<f64>().ok().filter(|number| number.is_finite())
+}
+
+// 正在进行的录制
+pub struct Recorder {
+    pub replay: Replay,
+    paddle_x: f64,  // 上一次记录的挡板位置
+    direction: f64, // 上一次记录的键盘方向
+}
+
+impl Recorder {
+    pub fn new(seed: u32, starting_lives: u32) -> Self {
+        // 初始值用NaN，保证第一次同步时一定会记下挡板位置和方向
+        Recorder { replay: Replay::new(seed, starting_lives), paddle_x: f64::NAN, direction: f64::NAN }
+    }
+
+    // 记下自上一次记录以来挡板位置和方向的变化
+    fn sync(&mut self, paddle_x: f64, direction: f64) {
+        if paddle_x != self.paddle_x {
+            self.paddle_x = paddle_x;
+            self.replay.records.push(Record::Paddle(paddle_x));
+        }
+        if direction != self.direction {
+            self.direction = direction;
+            self.replay.records.push(Record::Direction(direction));
+        }
+    }
+
+    // 在物理步开始前调用，记下这一步之前的输入并把物理步计入录像
+    pub fn tick(&mut self, paddle_x: f64, direction: f64) {
+        self.sync(paddle_x, direction);
+        match self.replay.records.last_mut() {
+            Some(Record::Ticks(count)) => *count += 1,
+            _ => self.replay.records.push(Record::Ticks(1)),
+        }
+    }
+
+    // 在物理步结束后调用：键盘控制和发球会在物理步内移动挡板，回放时同样会发生，不需要记录
+    pub fn settle(&mut self, paddle_x: f64) {
+        self.paddle_x = paddle_x;
+    }
+
+    pub fn click(&mut self, paddle_x: f64, direction: f64) {
+        self.sync(paddle_x, direction);
+        self.replay.records.push(Record::Click);
+    }
+}
+
+// 正在进行的回放
+pub struct Playback {
+    pub seed: u32,           // 录像那一局的种子和初始生命数，只用于回放，不改变玩家自己的设置
+    pub starting_lives: u32,
+    records: Vec<Record>,
+    position: usize, // 下一条要处理的记录
+    ticks_done: u32, // 当前这条Ticks记录已经推进了几个物理步
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Playback {
+            seed: replay.seed,
+            starting_lives: replay.starting_lives.max(1),
+            records: replay.records,
+            position: 0,
+            ticks_done: 0,
+        }
+    }
+
+    // 取出下一个物理步之前的一条输入，下一条是物理步或者已经放完时返回None
+    pub fn next_input(&mut self) -> Option<Record> {
+        match self.records.get(self.position) {
+            Some(Record::Ticks(_)) | None => None,
+            Some(record) => {
+                self.position += 1;
+                Some(*record)
+            }
+        }
+    }
+
+    // 消耗一个物理步，没有物理步可以消耗时返回false
+    pub fn take_tick(&mut self) -> bool {
+        let Some(Record::Ticks(count)) = self.records.get(self.position) else {
+            return false;
+        };
+
+        self.ticks_done += 1;
+        if self.ticks_done >= *count {
+            self.position += 1;
+            self.ticks_done = 0;
+        }
+        true
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.records.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_and_decode_round_trip() {
+        let mut replay = Replay::new(20240501, 3);
+        replay.records = vec![Record::Paddle(180.0), Record::Direction(0.0), Record::Ticks(95), Record::Click, Record::Paddle(200.125)];
+
+        let text = replay.encode();
+        assert_eq!(text, "PB1 20240501 3 p180 d0 t95 c p200.125");
+
+        let decoded = Replay::decode(&text).unwrap();
+        assert_eq!(decoded.seed, 20240501);
+        assert_eq!(decoded.starting_lives, 3);
+        assert_eq!(decoded.records, replay.records);
+    }
+
+    #[test]
+    fn decode_rejects_bad_input() {
+        assert_eq!(Replay::decode("PB2 1 3").unwrap_err(), ReplayError::UnsupportedVersion("PB2".to_string()));
+        assert_eq!(Replay::decode("PB1 1").unwrap_err(), ReplayError::MissingHeader);
+        assert_eq!(Replay::decode("PB1 1 3 x5").unwrap_err(), ReplayError::InvalidToken("x5".to_string()));
+        assert_eq!(Replay::decode("PB1 1 3 pNaN").unwrap_err(), ReplayError::InvalidToken("pNaN".to_string()));
+        assert_eq!(Replay::decode("PB1 1 3 c1").unwrap_err(), ReplayError::InvalidToken("c1".to_string()));
+        // 以多字节字符开头的记录同样是错误，而不是panic
+        assert_eq!(Replay::decode("PB1 1 3 é").unwrap_err(), ReplayError::InvalidToken("é".to_string()));
+    }
+
+    #[test]
+    fn recorder_merges_ticks_and_records_only_changes() {
+        let mut recorder = Recorder::new(1, 3);
+        recorder.tick(100.0, 0.0);
+        recorder.settle(100.0);
+        recorder.tick(100.0, 0.0);
+        recorder.settle(100.0);
+        recorder.click(100.0, 1.0);
+        recorder.tick(100.0, 1.0);
+
+        assert_eq!(
+            recorder.replay.records,
+            vec![Record::Paddle(100.0), Record::Direction(0.0), Record::Ticks(2), Record::Direction(1.0), Record::Click, Record::Ticks(1)]
+        );
+    }
+
+    #[test]
+    fn playback_returns_inputs_between_ticks() {
+        let mut replay = Replay::new(1, 3);
+        replay.records = vec![Record::Paddle(50.0), Record::Ticks(2), Record::Click];
+        let mut playback = Playback::new(replay);
+
+        assert_eq!(playback.next_input(), Some(Record::Paddle(50.0)));
+        assert_eq!(playback.next_input(), None);
+        assert!(playback.take_tick());
+        assert!(playback.take_tick());
+        assert_eq!(playback.next_input(), Some(Record::Click));
+        assert!(playback.is_finished());
+        assert!(!playback.take_tick());
+    }
+}
@@ -0,0 +1,28 @@
+// 球速：发球时使用本关的最低速度，之后随着碰到挡板、持续飞行和第一次到达顶部几行砖块逐渐加快，
+// 但不会超过本关的最高速度
+pub const PADDLE_HIT_SPEEDUP: f64 = 5.0; // 每次碰到挡板增加的速度（像素/秒）
+pub const SPEEDUP_PER_SECOND: f64 = 2.0; // 飞行中每秒增加的速度（像素/秒）
+pub const TOP_ROWS: usize = 2; // 球第一次到达最上面这几行砖块时加速
+pub const TOP_ROWS_SPEEDUP: f64 = 30.0; // 到达顶部几行时增加的速度（像素/秒）
+
+// 一关的球速范围（像素/秒）
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SpeedRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+// 每一关的球速范围，超过表中的关卡后沿用最后一项
+const LEVEL_SPEEDS: [SpeedRange; 5] = [
+    SpeedRange { min: 300.0, max: 420.0 },
+    SpeedRange { min: 320.0, max: 460.0 },
+    SpeedRange { min: 340.0, max: 500.0 },
+    SpeedRange { min: 360.0, max: 540.0 },
+    SpeedRange { min: 380.0, max: 580.0 },
+];
+
+// 取得关卡（从1开始）的球速范围
+pub fn speed_range(level: u32) -> SpeedRange {
+    let index = (level.max(1) as usize - 1).min(LEVEL_SPEEDS.len() - 1);
+    LEVEL_SPEEDS[index]
+}